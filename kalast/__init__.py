@@ -5,6 +5,7 @@ from kalast import (  # noqa
     entity,
     math,
     mesh,
+    routines,
     spice,
     tpm,
     util,
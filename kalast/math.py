@@ -8,4 +8,5 @@ from kalast._rs.math import (  # noqa
     simpson_1_3,
     simpson_3_8,
     boole,
+    tridiagonal,
 )
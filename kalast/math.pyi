@@ -7,4 +7,7 @@ def flattening_radius(radii: numpy.array) -> float: ...
 def trapez(y: numpy.array, x: numpy.array) -> float: ...
 def simpson_1_3(y: numpy.array, x: numpy.array) -> float: ...
 def simpson_3_8(y: numpy.array, x: numpy.array) -> float: ...
-def boole(y: numpy.array, x: numpy.array) -> float: ...
\ No newline at end of file
+def boole(y: numpy.array, x: numpy.array) -> float: ...
+def tridiagonal(
+    a: numpy.array, b: numpy.array, c: numpy.array, d: numpy.array
+) -> numpy.array: ...
\ No newline at end of file
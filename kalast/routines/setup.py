@@ -2,7 +2,10 @@ from kalast._rs.routines.setup import (  # noqa
     ProgressDebug,
     Time,
     SkinDepthParams,
+    SetupColumn,
+    Record,
     BodyDataMap,
+    BodyRecord,
     Body,
     Setup,
 )
@@ -26,37 +26,92 @@ class SkinDepthParams:
 
     def __init__(self, diffusivity: float = 0.0, period: float = 0.0) -> None: ...
 
+class SetupColumn:
+    dx: float
+
+    def __init__(self) -> None: ...
+    def set_depth_max(self, x: float) -> None: ...
+    def set_depth_max_skin_depth_1(self, n: int) -> None: ...
+    def set_depth_max_skin_depth_2pi(self) -> None: ...
+    def set_grid_uniform(self) -> None: ...
+    def set_grid_geometric(self, n: float = 5.0) -> None: ...
+    def set_layering_homogeneous(self) -> None: ...
+    def set_layering_mantle(
+        self, thickness: float, substrate: kalast.tpm.properties.Properties
+    ) -> None: ...
+    def set_layering_transition(
+        self, h: float, substrate: kalast.tpm.properties.Properties
+    ) -> None: ...
+    def set_bottom_adiabatic(self) -> None: ...
+    def set_bottom_flux(self, q: float) -> None: ...
+    def set_bottom_temperature(self, t: float) -> None: ...
+
+class Record:
+    temperature_surface: bool
+    flux_surface: bool
+    flux_mutual: bool
+    surface_facets: list[int] | str
+    temperature_interior: bool
+    interior_time_indices: list[int]
+
+    def __init__(self) -> None: ...
+    def set_surface_facets_all(self) -> None: ...
+
 class BodyDataMap:
     temperatures: list[numpy.array]
     thermal_properties_all: int
     thermal_properties_map: list[tuple[int, int]]
+    record: Record
 
     def __init__(
         self,
         temperatures: list[numpy.array] = [],
         thermal_properties_all: int = 0,
         thermal_properties_map: list[tuple[int, int]] = [],
+        record: Record | None = None,
     ) -> None: ...
+    def set_initial_effective(self) -> None: ...
+    def set_initial_diurnal(self, n: int) -> None: ...
+
+class BodyRecord:
+    facets: list[int]
+    time: numpy.ndarray
+    temperature_surface: numpy.ndarray
+    flux_surface: numpy.ndarray
+    flux_mutual: numpy.ndarray
+    interior_time_indices: list[int]
+    temperature_interior: list[list[numpy.ndarray]]
 
 class Body:
+    mesh: kalast.mesh.Mesh
+    state: numpy.ndarray
     spin_period: float
+    spin_axis: list[float]
     orbit_period: float
+    orbit_axis: list[float]
 
     def __init__(
         self,
+        mesh: kalast.mesh.Mesh | None = None,
+        state: numpy.ndarray | None = None,
         spin_period: float = 0.0,
+        spin_axis: list[float] = [0.0, 0.0, 1.0],
         orbit_period: float = 0.0,
+        orbit_axis: list[float] = [0.0, 0.0, 1.0],
     ) -> None: ...
-
-# class Record:
-#     temperature_surface: bool
-#     flux_surface: bool
-#     surface_facets: list[int] | str
-#     temperature_interior: bool
-#     interior_time_indices: list[int]
-#
-#     def __init__(self) -> None: ...
-#     def set_surface_facets_all(self) -> None: ...
+    def set_interior_column(self, z: list[float]) -> None: ...
+    def set_interior_setup(self, setup: SetupColumn) -> None: ...
+    def set_self_heating(
+        self,
+        occlusion: bool = True,
+        scattering: int = 1,
+        thermal: bool = True,
+        cutoff: float = 0.0,
+        cache: str | None = None,
+        rays: int | None = None,
+        seed: int = 0,
+    ) -> None: ...
+    def set_self_heating_none(self) -> None: ...
 
 class Setup:
     sun_position: numpy.ndarray
@@ -65,6 +120,59 @@ class Setup:
     bodies_data_map: list[BodyDataMap]
     progress_debug: ProgressDebug
     time: Time
+    threads: int
+    elapsed: float | None
 
     def __init__(self) -> None: ...
+    def set_scheme_explicit(self) -> None: ...
+    def set_scheme_implicit(self, theta: float = 0.5) -> None: ...
+    def set_solver(
+        self,
+        tolerance: float | None = None,
+        fallback: str | tuple[float, float] | None = None,
+    ) -> None: ...
+    def set_spinup(self, tolerance: float = 0.1, max_rotations: int = 100) -> None: ...
+    def set_spinup_none(self) -> None: ...
+    def set_illumination(
+        self,
+        cast: bool = True,
+        subdivisions: int = 1,
+        sun_samples: int = 1,
+        ellipsoids: list[kalast.entity.Body] = [],
+        trans_ellipsoids: list[numpy.ndarray] = [],
+    ) -> None: ...
+    def set_illumination_none(self) -> None: ...
+    def set_mutual_heating(
+        self,
+        scattering: bool = True,
+        thermal: bool = True,
+        occlusion: bool = True,
+        cutoff: float = 0.0,
+    ) -> None: ...
+    def set_mutual_heating_none(self) -> None: ...
+    def set_planet(
+        self,
+        body: kalast.entity.Body,
+        state: numpy.ndarray | None = None,
+        albedo: float = 0.25,
+        emissivity: float = 0.95,
+        temperature: float | None = None,
+        night: float = 0.0,
+        resolution: int = 32,
+        cast: bool = True,
+    ) -> None: ...
+    def set_planet_none(self) -> None: ...
     def prepare(self) -> None: ...
+    def prepare_from_checkpoint(self, path: str) -> None: ...
+    def save_checkpoint(self, path: str) -> None: ...
+    def load_checkpoint(self, path: str) -> None: ...
+    def set_checkpoint(self, every: int, path: str) -> None: ...
+    def set_checkpoint_none(self) -> None: ...
+    def step(self) -> bool: ...
+    def run(self) -> list[BodyRecord]: ...
+    def temperature_surface(self, body: int) -> numpy.ndarray: ...
+    def interior_t(self, body: int) -> numpy.ndarray: ...
+    def interior_d(self, body: int) -> numpy.ndarray: ...
+    def interior_p(self, body: int) -> numpy.ndarray: ...
+    def interior_c(self, body: int) -> numpy.ndarray: ...
+    def interior_k(self, body: int) -> numpy.ndarray: ...
@@ -4,6 +4,13 @@ from kalast.tpm import (  # noqa
     properties,
     emit,
     routine,
+    analytical,
+    heating,
+    illumination,
+    planetshine,
+    recoil,
+    response,
+    simple,
     #
     nonuniform,
     implicit,
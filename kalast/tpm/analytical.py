@@ -0,0 +1,11 @@
+from kalast._rs.tpm.analytical import (  # noqa
+    half_space_step,
+    half_space_flux,
+    periodic_surface,
+    periodic_radiation,
+    verify_half_space_step,
+    verify_half_space_flux,
+    verify_periodic_surface,
+    verify_periodic_radiation,
+    convergence_order,
+)
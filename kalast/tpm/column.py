@@ -1,3 +1,10 @@
 from kalast._rs.tpm.column import (  # noqa
     Column,
+    Interior,
+    depth_uniform,
+    depth_geometric,
+    depth_stencil,
+    stability_maxdt_column,
+    transition_layers,
+    temperature_profile,
 )
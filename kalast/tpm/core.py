@@ -10,5 +10,13 @@ from kalast._rs.tpm.core import (  # noqa
     newton_method_fn,
     newton_method_dfn,
     newton_method,
+    surface_gradient,
+    newton_method_gradient,
     conduction_1d,
+    conduction_1d_implicit,
+    depth_steps_nonuniform,
+    conduction_1d_nonuniform,
+    conduction_1d_implicit_nonuniform,
+    conduction_1d_variable,
+    conduction_1d_implicit_variable,
 )
@@ -6,4 +6,13 @@ from kalast._rs.tpm.emit import (  # noqa
     irradiance,
     reflectance,
     radiance,
+    jansky,
+    spectral_flux_density,
+    light_curve,
+    planck_inverse,
+    brightness_temperature,
+    brightness_temperature_array,
+    band_brightness_temperature,
+    band_brightness_temperature_array,
+    band_reflectance,
 )
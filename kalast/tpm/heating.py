@@ -0,0 +1,11 @@
+from kalast._rs.tpm.heating import (  # noqa
+    ViewFactors,
+    view_factor_matrix,
+    view_factors_monte_carlo,
+    scattered_irradiance,
+    thermal_irradiance,
+    self_heating,
+    view_factor_matrix_bodies,
+    mutual_heating,
+    mesh_hash,
+)
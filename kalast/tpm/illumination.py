@@ -0,0 +1,4 @@
+from kalast._rs.tpm.illumination import (  # noqa
+    illumination,
+    visible_sun_fraction,
+)
@@ -0,0 +1,3 @@
+from kalast._rs.tpm.planetshine import (  # noqa
+    planetshine,
+)
@@ -9,5 +9,9 @@ from kalast._rs.tpm.properties import (  # noqa
     thermal_inertia,
     skin_depth_1,
     skin_depth_2pi,
+    r350,
+    conductivity_radiative,
+    heat_capacity_polynomial,
+    HEAT_CAPACITY_MOON,
     Properties,
 )
@@ -3,6 +3,9 @@ def conductivity(ti: float, p: float, c: float) -> float: ...
 def diffusivity(k: float, p: float, c: float) -> float: ...
 def skin_depth_1(d: float, p: float) -> float: ...
 def skin_depth_2pi(d: float, p: float) -> float: ...
+def r350(chi: float = 2.7) -> float: ...
+def conductivity_radiative(k: float, t: float, r: float) -> float: ...
+def heat_capacity_polynomial(c: list[float], t: float) -> float: ...
 
 class Properties:
     albedo: float
@@ -12,8 +15,13 @@ class Properties:
     thermal_inertia: float
     conductivity: float
     diffusivity: float
+    conductivity_radiative: float | None
+    heat_capacity_polynomial: list[float] | None
 
     def __init__(self) -> None: ...
+    def conductivity_at(self, t: float) -> float: ...
+    def heat_capacity_at(self, t: float) -> float: ...
+    def diffusivity_at(self, t: float) -> float: ...
     def compute_thermal_inertia(self) -> None: ...
     def compute_conductivity(self) -> None: ...
     def compute_diffusivity(self) -> None: ...
@@ -24,3 +32,4 @@ DIMORPHOS: Properties
 MOON: Properties
 PHOBOS: Properties
 DEIMOS: Properties
+HEAT_CAPACITY_MOON: list[float]
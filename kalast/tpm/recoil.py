@@ -0,0 +1,6 @@
+from kalast._rs.tpm.recoil import (  # noqa
+    recoil,
+    recoil_mean,
+    yarkovsky_drift,
+    yorp_acceleration,
+)
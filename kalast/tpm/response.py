@@ -0,0 +1,4 @@
+from kalast._rs.tpm.response import (  # noqa
+    SpectralResponse,
+    BandPlanck,
+)
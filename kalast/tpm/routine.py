@@ -1,3 +1,6 @@
 from kalast._rs.tpm.routine import (  # noqa
     update_thermal_state,
+    update_thermal_state_nonuniform,
+    update_thermal_state_properties,
+    update_thermal_state_layered,
 )
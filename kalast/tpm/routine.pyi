@@ -1 +1,64 @@
-# def update_thermal_state(k: float, p: float, c: float) -> float: ...
\ No newline at end of file
+import numpy
+from kalast.tpm.properties import Properties
+
+def update_thermal_state(
+    t: numpy.ndarray,
+    f: float,
+    d: numpy.ndarray,
+    dtpdx2: numpy.ndarray,
+    se: float,
+    k: float,
+    twodx: float,
+    theta: float | None = None,
+    bottom_flux: float | None = None,
+    bottom_temperature: float | None = None,
+    tolerance: float | None = None,
+    fallback: str | tuple[float, float] | None = None,
+) -> numpy.ndarray: ...
+def update_thermal_state_nonuniform(
+    t: numpy.ndarray,
+    f: float,
+    d: numpy.ndarray,
+    dtpa: numpy.ndarray,
+    dtpb: numpy.ndarray,
+    se: float,
+    k: float,
+    g: tuple[float, float, float],
+    dxb: float,
+    theta: float | None = None,
+    bottom_flux: float | None = None,
+    bottom_temperature: float | None = None,
+    tolerance: float | None = None,
+    fallback: str | tuple[float, float] | None = None,
+) -> numpy.ndarray: ...
+def update_thermal_state_properties(
+    t: numpy.ndarray,
+    f: float,
+    properties: Properties,
+    dtpa: numpy.ndarray,
+    dtpb: numpy.ndarray,
+    g: tuple[float, float, float],
+    dxb: float,
+    theta: float | None = None,
+    bottom_flux: float | None = None,
+    bottom_temperature: float | None = None,
+    tolerance: float | None = None,
+    fallback: str | tuple[float, float] | None = None,
+) -> numpy.ndarray: ...
+def update_thermal_state_layered(
+    t: numpy.ndarray,
+    f: float,
+    p: numpy.ndarray,
+    c: numpy.ndarray,
+    k: numpy.ndarray,
+    properties: Properties,
+    dtpa: numpy.ndarray,
+    dtpb: numpy.ndarray,
+    g: tuple[float, float, float],
+    dxb: float,
+    theta: float | None = None,
+    bottom_flux: float | None = None,
+    bottom_temperature: float | None = None,
+    tolerance: float | None = None,
+    fallback: str | tuple[float, float] | None = None,
+) -> numpy.ndarray: ...
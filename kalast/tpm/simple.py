@@ -0,0 +1,7 @@
+from kalast._rs.tpm.simple import (  # noqa
+    bond_albedo,
+    diameter,
+    subsolar_temperature,
+    flux,
+    flux_ellipsoid,
+)
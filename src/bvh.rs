@@ -0,0 +1,418 @@
+use std::sync::OnceLock;
+
+use crate::{Float, Vec2, Vec3};
+
+// Triangles per leaf, nodes with more triangles are split.
+pub const LEAF_SIZE: usize = 4;
+
+// Maximum depth of the traversal, the median split keeps the tree balanced so it is never reached
+// for meshes that fit in memory.
+const STACK_SIZE: usize = 64;
+
+// Intersection of a ray with a triangle of a mesh.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub facet: usize,
+
+    // distance from the origin of the ray along its direction
+    pub distance: Float,
+
+    // barycentric coordinates (u, v) of the point with respect to the second and third vertices
+    // of the facet, the point is a + u (b - a) + v (c - a)
+    pub barycentric: Vec2,
+}
+
+impl std::fmt::Debug for Hit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Hit(facet={}, distance={}, barycentric={})",
+            self.facet, self.distance, self.barycentric,
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+
+    // first child for internal nodes (the second follows it), first triangle for leaves
+    index: u32,
+
+    // number of triangles, 0 for internal nodes
+    count: u32,
+}
+
+impl Node {
+    const EMPTY: Self = Self {
+        min: Vec3::ZERO,
+        max: Vec3::ZERO,
+        index: 0,
+        count: 0,
+    };
+}
+
+// Bounding volume hierarchy over the triangles of a mesh for ray queries.
+// Nodes are split at the median of the centroids of their triangles along the longest axis.
+// Children are always stored after their parent.
+#[derive(Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+
+    // triangles in the order of the leaves
+    triangles: Vec<[Vec3; 3]>,
+
+    // facet of each triangle
+    facets: Vec<u32>,
+}
+
+impl Bvh {
+    pub fn new(triangles: &[[Vec3; 3]]) -> Self {
+        let centroids: Vec<Vec3> = triangles
+            .iter()
+            .map(|[a, b, c]| (a + b + c) / 3.0)
+            .collect();
+        let mut facets: Vec<u32> = (0..triangles.len() as u32).collect();
+        let mut nodes = vec![Node::EMPTY];
+        if !triangles.is_empty() {
+            build(&mut nodes, 0, &mut facets, 0, triangles, &centroids);
+        }
+        let triangles = facets.iter().map(|&ii| triangles[ii as usize]).collect();
+        Self {
+            nodes,
+            triangles,
+            facets,
+        }
+    }
+
+    // Update the bounds for new positions of the same triangles, keeping the tree.
+    // Queries stay exact but become slower if the triangles moved a lot relative to each other,
+    // build a new hierarchy in that case.
+    pub fn refit(&mut self, triangles: &[[Vec3; 3]]) {
+        for (tri, &ii) in self.triangles.iter_mut().zip(&self.facets) {
+            *tri = triangles[ii as usize];
+        }
+        for ii in (0..self.nodes.len()).rev() {
+            let node = self.nodes[ii];
+            let (min, max) = match node.count {
+                0 if self.triangles.is_empty() => (Vec3::ZERO, Vec3::ZERO),
+                0 => {
+                    let (a, b) = (
+                        &self.nodes[node.index as usize],
+                        &self.nodes[node.index as usize + 1],
+                    );
+                    (a.min.min(b.min), a.max.max(b.max))
+                }
+                n => bounds(
+                    self.triangles[node.index as usize..(node.index + n) as usize]
+                        .iter()
+                        .flatten(),
+                ),
+            };
+            self.nodes[ii].min = min;
+            self.nodes[ii].max = max;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    // Closest intersection of a ray.
+    //
+    // p: origin of the ray
+    // u: direction of the ray, normalized
+    pub fn closest(&self, p: Vec3, u: Vec3) -> Option<Hit> {
+        let mut best = None;
+        self.traverse(p, u, Float::INFINITY, |hit| {
+            best = Some(hit);
+            Some(hit.distance)
+        });
+        best
+    }
+
+    // Any intersection of a ray closer than a distance, faster than the closest one for
+    // occlusion tests.
+    pub fn any(&self, p: Vec3, u: Vec3, max_distance: Float) -> Option<Hit> {
+        let mut found = None;
+        self.traverse(p, u, max_distance, |hit| {
+            found = Some(hit);
+            None
+        });
+        found
+    }
+
+    // All intersections of a ray, sorted by distance.
+    pub fn all(&self, p: Vec3, u: Vec3) -> Vec<Hit> {
+        let mut hits = vec![];
+        self.traverse(p, u, Float::INFINITY, |hit| {
+            hits.push(hit);
+            Some(Float::INFINITY)
+        });
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    // Visit the intersections closer than max_distance, in no particular order. The visitor
+    // returns the new maximum distance, or None to stop.
+    fn traverse<F>(&self, p: Vec3, u: Vec3, mut max_distance: Float, mut visit: F)
+    where
+        F: FnMut(Hit) -> Option<Float>,
+    {
+        if self.triangles.is_empty() {
+            return;
+        }
+        let inv = u.recip();
+        let mut stack = [0u32; STACK_SIZE];
+        let mut top = 1;
+        while top > 0 {
+            top -= 1;
+            let node = &self.nodes[stack[top] as usize];
+            if !intersect_box(node.min, node.max, p, inv, max_distance) {
+                continue;
+            }
+            if node.count == 0 {
+                stack[top] = node.index;
+                stack[top + 1] = node.index + 1;
+                top += 2;
+                continue;
+            }
+            for ii in node.index..node.index + node.count {
+                let [a, b, c] = &self.triangles[ii as usize];
+                let Some((t, bu, bv)) = intersect_triangle(p, u, *a, *b, *c) else {
+                    continue;
+                };
+                if t >= max_distance {
+                    continue;
+                }
+                let hit = Hit {
+                    facet: self.facets[ii as usize] as usize,
+                    distance: t,
+                    barycentric: Vec2::new(bu, bv),
+                };
+                match visit(hit) {
+                    Some(distance) => max_distance = distance,
+                    None => return,
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Bvh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bvh(triangles={}, nodes={})",
+            self.triangles.len(),
+            self.nodes.len(),
+        )
+    }
+}
+
+// Hierarchy of a mesh built at the first query. It is not part of the comparison of meshes.
+#[derive(Clone, Default)]
+pub struct BvhCache(OnceLock<Bvh>);
+
+impl BvhCache {
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    pub fn get_or_build<F: FnOnce() -> Bvh>(&self, build: F) -> &Bvh {
+        self.0.get_or_init(build)
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut Bvh> {
+        self.0.get_mut()
+    }
+
+    pub fn reset(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl PartialEq for BvhCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+fn build(
+    nodes: &mut Vec<Node>,
+    node: usize,
+    facets: &mut [u32],
+    start: usize,
+    triangles: &[[Vec3; 3]],
+    centroids: &[Vec3],
+) {
+    let (min, max) = bounds(facets.iter().flat_map(|&ii| &triangles[ii as usize]));
+    nodes[node].min = min;
+    nodes[node].max = max;
+
+    let (cmin, cmax) = bounds(facets.iter().map(|&ii| &centroids[ii as usize]));
+    let extent = cmax - cmin;
+    let axis = match extent {
+        e if e.x >= e.y && e.x >= e.z => 0,
+        e if e.y >= e.z => 1,
+        _ => 2,
+    };
+    // identical centroids cannot be split
+    if facets.len() <= LEAF_SIZE || extent[axis] <= 0.0 {
+        nodes[node].index = start as u32;
+        nodes[node].count = facets.len() as u32;
+        return;
+    }
+
+    let mid = facets.len() / 2;
+    facets.select_nth_unstable_by(mid, |&a, &b| {
+        centroids[a as usize][axis].total_cmp(&centroids[b as usize][axis])
+    });
+    let left = nodes.len();
+    nodes.push(Node::EMPTY);
+    nodes.push(Node::EMPTY);
+    nodes[node].index = left as u32;
+    nodes[node].count = 0;
+    let (lo, hi) = facets.split_at_mut(mid);
+    build(nodes, left, lo, start, triangles, centroids);
+    build(nodes, left + 1, hi, start + mid, triangles, centroids);
+}
+
+fn bounds<'a>(points: impl Iterator<Item = &'a Vec3>) -> (Vec3, Vec3) {
+    points.fold(
+        (
+            Vec3::splat(Float::INFINITY),
+            Vec3::splat(Float::NEG_INFINITY),
+        ),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    )
+}
+
+// Slab test of a ray against an axis-aligned box.
+//
+// inv: inverse of the direction of the ray
+fn intersect_box(min: Vec3, max: Vec3, p: Vec3, inv: Vec3, max_distance: Float) -> bool {
+    let (mut tmin, mut tmax): (Float, Float) = (0.0, max_distance);
+    for ii in 0..3 {
+        // ray parallel to the slab, inside it or not, (min - p) * inv is 0 * inf = NaN for a
+        // ray starting on one of its planes
+        if inv[ii].is_infinite() {
+            if p[ii] < min[ii] || p[ii] > max[ii] {
+                return false;
+            }
+            continue;
+        }
+        let t1 = (min[ii] - p[ii]) * inv[ii];
+        let t2 = (max[ii] - p[ii]) * inv[ii];
+        tmin = tmin.max(t1.min(t2));
+        tmax = tmax.min(t1.max(t2));
+    }
+    tmin <= tmax
+}
+
+// Möller–Trumbore intersection returning the distance and barycentric coordinates, see
+// mesh::intersect_triangle_moller_trumbore.
+pub fn intersect_triangle(
+    p: Vec3,
+    u: Vec3,
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+) -> Option<(Float, Float, Float)> {
+    let e1 = b - a;
+    let e2 = c - a;
+    let ray_cross_e2 = u.cross(e2);
+    let det = e1.dot(ray_cross_e2);
+    if det > -crate::util::EPSILON && det < crate::util::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = p - a;
+    let bu = inv_det * s.dot(ray_cross_e2);
+    if !(0.0..=1.0).contains(&bu) {
+        return None;
+    }
+    let s_cross_e1 = s.cross(e1);
+    let bv = inv_det * u.dot(s_cross_e1);
+    if bv < 0.0 || bu + bv > 1.0 {
+        return None;
+    }
+    let t = inv_det * e2.dot(s_cross_e1);
+    (t > crate::util::EPSILON).then_some((t, bu, bv))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Float, Vec3,
+        mesh::{Mesh, intersect_mesh, intersect_triangle_moller_trumbore},
+    };
+
+    // Distance to a facet hit by a ray, without hierarchy.
+    fn distance(mesh: &Mesh, facet: usize, p: Vec3, u: Vec3) -> Option<Float> {
+        let [a, b, c] = mesh.triangle(facet);
+        intersect_triangle_moller_trumbore(&p, &u, &a, &b, &c).map(|x| (x - p).length())
+    }
+
+    // Compare the closest and any facet hit by a ray with all the facets tested.
+    fn check(mesh: &Mesh, p: Vec3, u: Vec3) {
+        let expected = (0..mesh.facets.len())
+            .filter_map(|ii| distance(mesh, ii, p, u).map(|d| (ii, d)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let closest = intersect_mesh(mesh, &p, &u, false);
+        let any = intersect_mesh(mesh, &p, &u, true);
+        match (expected, closest, any) {
+            (None, None, None) => {}
+            (Some((_, d)), Some((closest, x)), Some((any, _))) => {
+                // facets sharing the point hit are as close as the expected one
+                assert!(((x - p).length() - d).abs() < 1e-5, "{} {}", p, u);
+                assert!(
+                    distance(mesh, closest, p, u).is_some_and(|dc| (dc - d).abs() < 1e-5),
+                    "{} {}",
+                    p,
+                    u
+                );
+                assert!(distance(mesh, any, p, u).is_some(), "{} {}", p, u);
+            }
+            (expected, closest, any) => panic!(
+                "{} {}: expected {:?}, closest {:?}, any {:?}",
+                p, u, expected, closest, any
+            ),
+        }
+    }
+
+    #[test]
+    fn axis_aligned_rays() {
+        // rays along z starting above the vertices, and along x and y at their height, are
+        // parallel to the slabs of the boxes they start on
+        let mesh = Mesh::load("res/plane_crater_1024-5000_h=0.437.obj", |x| x);
+        for v in &mesh.vertices {
+            let v = v.pos;
+            check(&mesh, v + Vec3::Z, -Vec3::Z);
+            check(&mesh, Vec3::new(-1.0, v.y, v.z + 1e-3), Vec3::X);
+            check(&mesh, Vec3::new(v.x, 1.0, v.z + 1e-3), -Vec3::Y);
+        }
+    }
+
+    #[test]
+    fn rays_from_outside() {
+        // rays from points around an icosphere towards points spread inside it
+        let mesh = Mesh::load("res/ico4.obj", |x| x);
+        let golden = crate::util::PI * (3.0 - (5.0 as Float).sqrt());
+        let n = 500;
+        for ii in 0..n {
+            let z = 1.0 - 2.0 * (ii as Float + 0.5) / n as Float;
+            let r = (1.0 - z * z).sqrt();
+            let phi = golden * ii as Float;
+            let p = 2.0 * Vec3::new(r * phi.cos(), r * phi.sin(), z);
+            let target = 0.5 * Vec3::new(phi.sin(), z, r * phi.cos());
+            check(&mesh, p, (target - p).normalize());
+            check(&mesh, target, (p - target).normalize());
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
 use once_cell::sync::Lazy;
 
-use crate::{Float, UVec2, Vec3};
+use crate::{Float, UVec2, Vec3, tpm::response::SpectralResponse};
 
 pub const EARTH: Lazy<Body> = Lazy::new(|| Body {
     entity: Entity {
@@ -117,6 +120,7 @@ pub const TIRI: Lazy<Camera> = Lazy::new(|| Camera {
         "Filter f (13.0um)".to_string(),
         "Filter g (wide)".to_string(),
     ],
+    responses: BTreeMap::new(),
 });
 
 pub const AFC: Lazy<Camera> = Lazy::new(|| Camera {
@@ -129,6 +133,7 @@ pub const AFC: Lazy<Camera> = Lazy::new(|| Camera {
     px: UVec2::new(1024, 1024),
     fovy: 5.47,
     filters: vec![],
+    responses: BTreeMap::new(),
 });
 
 pub const HERA: Lazy<Spacecraft> = Lazy::new(|| Spacecraft {
@@ -250,6 +255,9 @@ pub struct Camera {
     pub px: UVec2,
     pub fovy: Float, // in degree
     pub filters: Vec<String>,
+
+    // spectral response of the filters, by name
+    pub responses: BTreeMap<String, SpectralResponse>,
 }
 
 impl Camera {
@@ -259,19 +267,37 @@ impl Camera {
             px: UVec2::ZERO,
             fovy: 0.0,
             filters: vec![],
+            responses: BTreeMap::new(),
         }
     }
 
     pub fn npx(&self) -> usize {
         self.px.element_product()
     }
+
+    pub fn response(&self, filter: &str) -> Option<&SpectralResponse> {
+        self.responses.get(filter)
+    }
+
+    // Attach a spectral response to a filter of the camera.
+    pub fn set_response(&mut self, filter: &str, response: SpectralResponse) -> Result<()> {
+        if !self.filters.iter().any(|f| f == filter) {
+            return Err(anyhow!(
+                "Camera {} has no filter {}",
+                self.entity.name,
+                filter
+            ));
+        }
+        self.responses.insert(filter.to_string(), response);
+        Ok(())
+    }
 }
 
 impl std::fmt::Debug for Camera {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Camera(id={}, name={}, frame={}, label={}, px={}, fovy={}, filters={:?})",
+            "Camera(id={}, name={}, frame={}, label={}, px={}, fovy={}, filters={:?}, responses={:?})",
             self.entity.id,
             self.entity.name,
             self.entity.frame,
@@ -279,6 +305,7 @@ impl std::fmt::Debug for Camera {
             self.px,
             self.fovy,
             self.filters,
+            self.responses.keys().collect::<Vec<_>>(),
         )
     }
 }
@@ -3,6 +3,7 @@ pub mod entity;
 pub mod app;
 pub mod math;
 pub mod mesh;
+pub mod bvh;
 pub mod py;
 pub mod routines;
 pub mod spice;
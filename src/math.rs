@@ -1,5 +1,5 @@
 use glam::Vec3Swizzles;
-use numpy::ndarray::ArrayView1;
+use numpy::ndarray::{Array1, ArrayView1};
 
 use crate::{Float, Vec3};
 
@@ -78,6 +78,72 @@ pub fn boole(y: ArrayView1<Float>, x: ArrayView1<Float>) -> Float {
     r
 }
 
+pub fn tridiagonal(
+    a: ArrayView1<Float>,
+    b: ArrayView1<Float>,
+    c: ArrayView1<Float>,
+    d: ArrayView1<Float>,
+) -> Array1<Float> {
+    // Thomas algorithm, solve a tridiagonal system of equations.
+    //
+    // a: sub-diagonal (a[0] is not used)
+    // b: diagonal
+    // c: super-diagonal (c[n-1] is not used)
+    // d: right-hand side
+    let (a, b) = (a.as_standard_layout(), b.as_standard_layout());
+    let mut cp = c.to_vec();
+    let mut dp = d.to_vec();
+    tridiagonal_inplace(
+        a.as_slice().unwrap(),
+        b.as_slice().unwrap(),
+        &mut cp,
+        &mut dp,
+    );
+    Array1::from_vec(dp)
+}
+
+pub fn tridiagonal_inplace(a: &[Float], b: &[Float], c: &mut [Float], d: &mut [Float]) {
+    // Same as tridiagonal without allocation, c is overwritten and d receives the solution.
+    let n = d.len();
+
+    c[0] /= b[0];
+    d[0] /= b[0];
+
+    for ii in 1..n {
+        let m = b[ii] - a[ii] * c[ii - 1];
+        if ii < n - 1 {
+            c[ii] /= m;
+        }
+        d[ii] = (d[ii] - a[ii] * d[ii - 1]) / m;
+    }
+
+    for ii in (0..n - 1).rev() {
+        d[ii] -= c[ii] * d[ii + 1];
+    }
+}
+
+pub fn erfc(x: Float) -> Float {
+    // Complementary error function, Chebyshev fit of Numerical Recipes (erfcc), fractional error
+    // below 1.2e-7 everywhere.
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.2655122
+            + t * (1.0000237
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.135204
+                                    + t * (1.4885159 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+pub fn erf(x: Float) -> Float {
+    1.0 - erfc(x)
+}
+
 pub(crate) mod py {
     use numpy::{PyReadonlyArray1, ToPyArray};
     use pyo3::prelude::*;
@@ -149,6 +215,17 @@ pub(crate) mod py {
         Ok(super::simpson_3_8(y.as_array(), x.as_array()))
     }
 
+    #[pyfunction]
+    pub fn tridiagonal<'py>(
+        py: Python<'py>,
+        a: PyReadonlyArray1<'py, Float>,
+        b: PyReadonlyArray1<'py, Float>,
+        c: PyReadonlyArray1<'py, Float>,
+        d: PyReadonlyArray1<'py, Float>,
+    ) -> pyo3::Bound<'py, numpy::PyArray1<Float>> {
+        super::tridiagonal(a.as_array(), b.as_array(), c.as_array(), d.as_array()).to_pyarray(py)
+    }
+
     #[pyfunction]
     pub fn boole(
         y: PyReadonlyArray1<'_, Float>,
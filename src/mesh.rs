@@ -180,6 +180,9 @@ pub struct Mesh {
 
     // temporary until better solution is found
     pub(crate) _vertices_before_flatten: Vec<Vertex>,
+
+    // acceleration of ray queries, built at the first query
+    pub(crate) bvh: crate::bvh::BvhCache,
 }
 
 impl Mesh {
@@ -190,6 +193,7 @@ impl Mesh {
             facets: vec![],
             material_id: None,
             _vertices_before_flatten: vec![],
+            bvh: crate::bvh::BvhCache::new(),
         }
     }
 
@@ -411,8 +415,20 @@ impl Mesh {
 
     // Recompute facets (pos, normal, area) from current vertices positions and indices.
     // Call after mutating vertex positions in place, since facets are not kept in sync automatically.
+    // The hierarchy for ray queries is refit if the number of facets did not change, built again
+    // at the next query otherwise.
     pub fn recompute_facets(&mut self) {
         self.facets = compute_facets(&self.vertices, &self.indices);
+        let triangles = self.triangles();
+        match self.bvh.get_mut() {
+            Some(bvh) if bvh.len() == triangles.len() => bvh.refit(&triangles),
+            _ => self.bvh.reset(),
+        }
+    }
+
+    // Build again the hierarchy for ray queries, after the vertices changed a lot.
+    pub fn rebuild_bvh(&mut self) {
+        self.bvh.reset();
     }
 
     pub fn is_flat(&self) -> bool {
@@ -458,9 +474,64 @@ impl Mesh {
         }
     }
 
+    // Positions of the vertices of each facet.
+    pub fn triangles(&self) -> Vec<[Vec3; 3]> {
+        if self.is_flat() {
+            self.vertices
+                .chunks(3)
+                .map(|c| [c[0].pos, c[1].pos, c[2].pos])
+                .collect()
+        } else {
+            self.indices
+                .chunks(3)
+                .map(|c| [c[0], c[1], c[2]].map(|ii| self.vertices[ii as usize].pos))
+                .collect()
+        }
+    }
+
+    // Vertices of a facet, see triangles.
+    pub fn triangle(&self, facet: usize) -> [Vec3; 3] {
+        if self.is_flat() {
+            [0, 1, 2].map(|ii| self.vertices[3 * facet + ii].pos)
+        } else {
+            [0, 1, 2].map(|ii| self.vertices[self.indices[3 * facet + ii] as usize].pos)
+        }
+    }
+
+    // Hierarchy for ray queries, built at the first call.
+    pub fn bvh(&self) -> &crate::bvh::Bvh {
+        self.bvh
+            .get_or_build(|| crate::bvh::Bvh::new(&self.triangles()))
+    }
+
+    // Closest facet hit by a ray (exit_first false) or any facet hit (exit_first true), and the
+    // intersection point.
     pub fn intersect(&self, p: &Vec3, u: &Vec3, exit_first: bool) -> Option<(usize, Vec3)> {
         intersect_mesh(self, p, u, exit_first)
     }
+
+    // Closest intersection of a ray with the mesh.
+    //
+    // p: origin of the ray
+    // u: direction of the ray, normalized
+    pub fn intersect_closest(&self, p: &Vec3, u: &Vec3) -> Option<crate::bvh::Hit> {
+        self.bvh().closest(*p, *u)
+    }
+
+    // Any intersection of a ray closer than max_distance, for occlusion tests.
+    pub fn intersect_any(
+        &self,
+        p: &Vec3,
+        u: &Vec3,
+        max_distance: Float,
+    ) -> Option<crate::bvh::Hit> {
+        self.bvh().any(*p, *u, max_distance)
+    }
+
+    // All intersections of a ray with the mesh, sorted by distance.
+    pub fn intersect_all(&self, p: &Vec3, u: &Vec3) -> Vec<crate::bvh::Hit> {
+        self.bvh().all(*p, *u)
+    }
 }
 
 impl std::fmt::Debug for Mesh {
@@ -638,6 +709,8 @@ impl Model {
 
                     // temporary until better solution is found
                     _vertices_before_flatten: vec![],
+
+                    bvh: crate::bvh::BvhCache::new(),
                 };
 
                 // Can now use normals per facet (if computed) to compute normals per vertex.
@@ -804,68 +877,14 @@ pub fn intersect_triangle_moller_trumbore(
     }
 }
 
-/* Put that in for loop after `intersect` calculation to debug
-println!(
-    "intersected #{} start: {:?}, intersected: {:?}, dist: {}, best dist found: {:?}",
-    ii,
-    p.as_slice(),
-    intersect.as_slice(),
-    (intersect - p).magnitude(),
-    best_intersect
-        .as_ref()
-        .and_then(|i| Some((i.0 - p).magnitude()))
-);
-*/
+// Intersection of a ray with a mesh, using the hierarchy of the mesh (see Mesh::bvh).
+// Returns the closest facet hit, or any facet hit if exit_first, and the intersection point.
 pub fn intersect_mesh(mesh: &Mesh, p: &Vec3, u: &Vec3, exit_first: bool) -> Option<(usize, Vec3)> {
-    let mut best_intersect: Option<(usize, Vec3)> = None;
-    let mut best_dist: Option<Float> = None;
-
-    let it: Vec<(&Vec3, &Vec3, &Vec3)> = {
-        if mesh.is_flat() {
-            mesh.vertices
-                .chunks(3)
-                .map(|c| (&c[0].pos, &c[1].pos, &c[2].pos))
-                .collect()
-        } else {
-            mesh.indices
-                .chunks(3)
-                .map(|c| {
-                    (
-                        &mesh.vertices[c[0] as usize].pos,
-                        &mesh.vertices[c[1] as usize].pos,
-                        &mesh.vertices[c[2] as usize].pos,
-                    )
-                })
-                .collect()
-        }
+    let hit = match exit_first {
+        true => mesh.intersect_any(p, u, Float::INFINITY),
+        false => mesh.intersect_closest(p, u),
     };
-
-    // println!("{} {}", p, u);
-
-    for (f, (a, b, c)) in it.iter().enumerate() {
-        // println!("{}: {}, {}, {}", f, a, b, c);
-        // &mesh.facets[f].n
-        if let Some(intersect) = intersect_triangle_moller_trumbore(p, u, a, b, c) {
-            let dist = (intersect - p).length();
-            // println!("found! dist={}", dist);
-
-            if let None = best_intersect {
-                best_intersect = Some((f, intersect));
-                best_dist = Some(dist);
-
-                if exit_first {
-                    return best_intersect;
-                }
-            } else if let Some(best_dist) = &mut best_dist {
-                if dist < *best_dist {
-                    best_intersect = Some((f, intersect));
-                    *best_dist = dist;
-                }
-            }
-        };
-    }
-
-    best_intersect
+    hit.map(|hit| (hit.facet, p + u * hit.distance))
 }
 
 /// Compute the view factor between a facet A and B with area of facet B.
@@ -903,7 +922,7 @@ pub fn view_factor_facets(face_a: &Facet, face_b: &Facet, trans_b2a: &Mat4) -> F
 
     // This is a condition on the relation between distance of the two facets and their surface area to avoid too large
     // view factor in case of very close distance.
-    // TODO: subdivide the facet and recompute.
+    // Only centres are known here, see view_factor_triangles for close facets.
     if distance_a2b < face_b.area.sqrt() {
         return 0.0;
     }
@@ -924,6 +943,62 @@ pub fn view_factor_facets(face_a: &Facet, face_b: &Facet, trans_b2a: &Mat4) -> F
     view_factor_scalar(angle_at_a, angle_at_b, distance_a2b)
 }
 
+// Facets closer than this many times their size are subdivided, see view_factor_triangles.
+pub const VIEW_FACTOR_NEAR_FIELD: Float = 2.0;
+
+// Maximum subdivisions of close facets, each one splits both facets in 4.
+pub const VIEW_FACTOR_MAX_DEPTH: usize = 3;
+
+/// View factor between triangles A and B by unit of area, as view_factor_facets.
+/// Close triangles are split in 4 at the middle of their edges and the view factor is the mean of
+/// the ones between the sub-triangles, recursively up to VIEW_FACTOR_MAX_DEPTH. This replaces the
+/// removal of close facets of view_factor_facets.
+///
+/// trans_b2a: transformation from the fixed frame of the body of B to the one of A
+pub fn view_factor_triangles(a: &[Vec3; 3], b: &[Vec3; 3], trans_b2a: &Mat4) -> Float {
+    let b = b.map(|p| trans_b2a.transform_point3(p));
+    view_factor_near_field(a, &b, 0)
+}
+
+fn view_factor_near_field(a: &[Vec3; 3], b: &[Vec3; 3], depth: usize) -> Float {
+    let (ab_a, ac_a) = (a[1] - a[0], a[2] - a[0]);
+    let (ab_b, ac_b) = (b[1] - b[0], b[2] - b[0]);
+    let vector_a2b = (b[0] + b[1] + b[2] - a[0] - a[1] - a[2]) / 3.0;
+    let distance_a2b = vector_a2b.length();
+    let size = area_facet(&ab_a, &ac_a)
+        .max(area_facet(&ab_b, &ac_b))
+        .sqrt();
+
+    if distance_a2b < VIEW_FACTOR_NEAR_FIELD * size && depth < VIEW_FACTOR_MAX_DEPTH {
+        let (sa, sb) = (subdivide_triangle(a), subdivide_triangle(b));
+        let sum = sa
+            .iter()
+            .flat_map(|a| sb.iter().map(move |b| (a, b)))
+            .map(|(a, b)| view_factor_near_field(a, b, depth + 1))
+            .sum::<Float>();
+        return sum / 16.0;
+    }
+    if distance_a2b == 0.0 {
+        return 0.0;
+    }
+
+    let unit_a2b = vector_a2b / distance_a2b;
+    let cos_a = normal_facet(&ab_a, &ac_a).dot(unit_a2b);
+    let cos_b = -normal_facet(&ab_b, &ac_b).dot(unit_a2b);
+    // also removes degenerate triangles without normal
+    if !(cos_a > 0.0 && cos_b > 0.0) {
+        return 0.0;
+    }
+    cos_a * cos_b / (crate::util::PI * distance_a2b.powi(2))
+}
+
+// Split a triangle in 4 triangles of equal area at the middle of its edges, same orientation.
+pub fn subdivide_triangle(t: &[Vec3; 3]) -> [[Vec3; 3]; 4] {
+    let [a, b, c] = *t;
+    let (ab, bc, ca) = ((a + b) / 2.0, (b + c) / 2.0, (c + a) / 2.0);
+    [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+}
+
 /// Largest slope angle of spherical segment, in radian.
 ///
 /// S: curvature diameter
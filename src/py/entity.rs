@@ -5,7 +5,7 @@ use pyo3::{prelude::*, types::PyList};
 
 use crate::{
     Float, entity::Body as RsBody, entity::Camera as RsCamera, entity::Entity as RsEntity,
-    entity::Spacecraft as RsSpacecraft,
+    entity::Spacecraft as RsSpacecraft, py::tpm::response::SpectralResponse,
 };
 
 #[pyclass(from_py_object, unsendable, dict)]
@@ -236,6 +236,7 @@ impl Camera {
                 px: px.into(),
                 fovy,
                 filters,
+                responses: std::collections::BTreeMap::new(),
             })),
         }
     }
@@ -309,6 +310,26 @@ impl Camera {
         self.inner.borrow().npx()
     }
 
+    #[getter]
+    fn filters(&self) -> Vec<String> {
+        self.inner.borrow().filters.clone()
+    }
+
+    fn response(&self, filter: &str) -> Option<SpectralResponse> {
+        self.inner
+            .borrow()
+            .response(filter)
+            .cloned()
+            .map(SpectralResponse::from_inner)
+    }
+
+    fn set_response(&self, filter: &str, response: PyRef<'_, SpectralResponse>) -> PyResult<()> {
+        self.inner
+            .borrow_mut()
+            .set_response(filter, response.inner.borrow().clone())
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:#}", e)))
+    }
+
     pub fn __repr__(&self) -> String {
         format!("{:?}", self.inner.borrow())
     }
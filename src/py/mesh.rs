@@ -539,6 +539,44 @@ impl Mesh {
             .map(|(i, x)| (i, x.into()))
     }
 
+    // Intersections are returned as (facet, distance, barycentric coordinates), see bvh::Hit.
+    fn intersect_closest(
+        &self,
+        p: [Float; 3],
+        u: [Float; 3],
+    ) -> Option<(usize, Float, [Float; 2])> {
+        self.inner
+            .borrow()
+            .intersect_closest(&p.into(), &u.into())
+            .map(hit_tuple)
+    }
+
+    #[pyo3(signature = (p, u, max_distance=Float::INFINITY))]
+    fn intersect_any(
+        &self,
+        p: [Float; 3],
+        u: [Float; 3],
+        max_distance: Float,
+    ) -> Option<(usize, Float, [Float; 2])> {
+        self.inner
+            .borrow()
+            .intersect_any(&p.into(), &u.into(), max_distance)
+            .map(hit_tuple)
+    }
+
+    fn intersect_all(&self, p: [Float; 3], u: [Float; 3]) -> Vec<(usize, Float, [Float; 2])> {
+        self.inner
+            .borrow()
+            .intersect_all(&p.into(), &u.into())
+            .into_iter()
+            .map(hit_tuple)
+            .collect()
+    }
+
+    fn rebuild_bvh(&mut self) {
+        self.inner.borrow_mut().rebuild_bvh();
+    }
+
     fn __repr__(&self) -> String {
         format!("{:?}", self.inner.borrow())
     }
@@ -565,6 +603,10 @@ crate::impl_mesh_field_vec!(FacetView, facets, pos);
 crate::impl_mesh_field_vec!(FacetView, facets, normal);
 crate::impl_mesh_field_scalar!(FacetView, facets, area, Float);
 
+fn hit_tuple(hit: crate::bvh::Hit) -> (usize, Float, [Float; 2]) {
+    (hit.facet, hit.distance, hit.barycentric.into())
+}
+
 #[pyclass(unsendable)]
 pub struct FacetVerticesView {
     pub mesh: Rc<RefCell<crate::mesh::Mesh>>,
@@ -799,6 +841,19 @@ pub fn view_factor_facets(
     )
 }
 
+#[pyfunction]
+pub fn view_factor_triangles(
+    a: [[Float; 3]; 3],
+    b: [[Float; 3]; 3],
+    trans_b2a: numpy::PyReadonlyArray2<'_, Float>,
+) -> Float {
+    crate::mesh::view_factor_triangles(
+        &a.map(Vec3::from),
+        &b.map(Vec3::from),
+        &crate::Mat4::from_cols_slice(trans_b2a.as_slice().unwrap()).transpose(),
+    )
+}
+
 #[pyfunction]
 pub fn rms_slope_terrain(
     theta: numpy::PyReadonlyArray1<'_, Float>,
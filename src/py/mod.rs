@@ -59,6 +59,7 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyadd_f!(math, crate::math::py::simpson_1_3);
     pyadd_f!(math, crate::math::py::simpson_3_8);
     pyadd_f!(math, crate::math::py::boole);
+    pyadd_f!(math, crate::math::py::tridiagonal);
     m.add_submodule(&math)?;
     py.import("sys")?
         .getattr("modules")?
@@ -123,6 +124,7 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyadd_f!(mesh, crate::mesh::view_factor_scalar_with_area);
     pyadd_f!(mesh, crate::mesh::view_factor_scalar);
     pyadd_f!(mesh, mesh::view_factor_facets);
+    pyadd_f!(mesh, mesh::view_factor_triangles);
     pyadd_f!(mesh, crate::mesh::largest_slope_angle_sphere);
     pyadd_f!(mesh, crate::mesh::curvature_radius);
     pyadd_f!(mesh, crate::mesh::curvature_diameter_from_radius);
@@ -163,7 +165,15 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyadd_f!(core, crate::tpm::core::newton_method_fn);
     pyadd_f!(core, crate::tpm::core::newton_method_dfn);
     pyadd_f!(core, crate::tpm::core::py::newton_method);
+    pyadd_f!(core, crate::tpm::core::surface_gradient);
+    pyadd_f!(core, crate::tpm::core::py::newton_method_gradient);
     pyadd_f!(core, crate::tpm::core::py::conduction_1d);
+    pyadd_f!(core, crate::tpm::core::py::conduction_1d_implicit);
+    pyadd_f!(core, crate::tpm::core::depth_steps_nonuniform);
+    pyadd_f!(core, crate::tpm::core::py::conduction_1d_nonuniform);
+    pyadd_f!(core, crate::tpm::core::py::conduction_1d_implicit_nonuniform);
+    pyadd_f!(core, crate::tpm::core::py::conduction_1d_variable);
+    pyadd_f!(core, crate::tpm::core::py::conduction_1d_implicit_variable);
     tpm.add_submodule(&core)?;
     py.import("sys")?
         .getattr("modules")?
@@ -181,6 +191,13 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyadd_f!(properties, crate::tpm::properties::thermal_inertia);
     pyadd_f!(properties, crate::tpm::properties::skin_depth_1);
     pyadd_f!(properties, crate::tpm::properties::skin_depth_2pi);
+    pyadd_f!(properties, crate::tpm::properties::r350);
+    pyadd_f!(properties, crate::tpm::properties::conductivity_radiative);
+    pyadd_f!(properties, crate::tpm::properties::py::heat_capacity_polynomial);
+    properties.add(
+        "HEAT_CAPACITY_MOON",
+        crate::tpm::properties::HEAT_CAPACITY_MOON.to_vec(),
+    )?;
     properties.add_class::<tpm::properties::Properties>()?;
     tpm.add_submodule(&properties)?;
     py.import("sys")?
@@ -189,6 +206,13 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
 
     let column = PyModule::new(tpm.py(), "column")?;
     column.add_class::<tpm::column::Column>()?;
+    column.add_class::<tpm::column::Interior>()?;
+    pyadd_f!(column, crate::tpm::column::py::depth_uniform);
+    pyadd_f!(column, crate::tpm::column::py::depth_geometric);
+    pyadd_f!(column, crate::tpm::column::py::depth_stencil);
+    pyadd_f!(column, crate::tpm::column::py::stability_maxdt_column);
+    pyadd_f!(column, crate::tpm::column::py::transition_layers);
+    pyadd_f!(column, crate::tpm::column::py::temperature_profile);
     tpm.add_submodule(&column)?;
     py.import("sys")?
         .getattr("modules")?
@@ -202,6 +226,15 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyadd_f!(emit, crate::tpm::emit::irradiance);
     pyadd_f!(emit, crate::tpm::emit::reflectance);
     pyadd_f!(emit, crate::tpm::emit::py::radiance);
+    pyadd_f!(emit, crate::tpm::emit::jansky);
+    pyadd_f!(emit, crate::tpm::emit::py::spectral_flux_density);
+    pyadd_f!(emit, crate::tpm::emit::py::light_curve);
+    pyadd_f!(emit, crate::tpm::emit::planck_inverse);
+    pyadd_f!(emit, crate::tpm::emit::brightness_temperature);
+    pyadd_f!(emit, crate::tpm::emit::py::brightness_temperature_array);
+    pyadd_f!(emit, crate::tpm::emit::py::band_brightness_temperature);
+    pyadd_f!(emit, crate::tpm::emit::py::band_brightness_temperature_array);
+    pyadd_f!(emit, crate::tpm::emit::py::band_reflectance);
     tpm.add_submodule(&emit)?;
     py.import("sys")?
         .getattr("modules")?
@@ -209,11 +242,88 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
 
     let routine = PyModule::new(tpm.py(), "routine")?;
     pyadd_f!(routine, crate::tpm::routine::py::update_thermal_state);
+    pyadd_f!(routine, crate::tpm::routine::py::update_thermal_state_nonuniform);
+    pyadd_f!(routine, crate::tpm::routine::py::update_thermal_state_properties);
+    pyadd_f!(routine, crate::tpm::routine::py::update_thermal_state_layered);
     tpm.add_submodule(&routine)?;
     py.import("sys")?
         .getattr("modules")?
         .set_item("kalast._rs.tpm.routine", routine)?;
 
+    let analytical = PyModule::new(tpm.py(), "analytical")?;
+    pyadd_f!(analytical, crate::tpm::analytical::py::half_space_step);
+    pyadd_f!(analytical, crate::tpm::analytical::py::half_space_flux);
+    pyadd_f!(analytical, crate::tpm::analytical::py::periodic_surface);
+    pyadd_f!(analytical, crate::tpm::analytical::py::periodic_radiation);
+    pyadd_f!(analytical, crate::tpm::analytical::py::verify_half_space_step);
+    pyadd_f!(analytical, crate::tpm::analytical::py::verify_half_space_flux);
+    pyadd_f!(analytical, crate::tpm::analytical::py::verify_periodic_surface);
+    pyadd_f!(analytical, crate::tpm::analytical::py::verify_periodic_radiation);
+    pyadd_f!(analytical, crate::tpm::analytical::py::convergence_order);
+    tpm.add_submodule(&analytical)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.analytical", analytical)?;
+
+    let heating = PyModule::new(tpm.py(), "heating")?;
+    heating.add_class::<tpm::viewfactors::ViewFactors>()?;
+    pyadd_f!(heating, crate::tpm::heating::py::view_factor_matrix);
+    pyadd_f!(heating, crate::tpm::heating::py::view_factors_monte_carlo);
+    pyadd_f!(heating, crate::tpm::heating::py::scattered_irradiance);
+    pyadd_f!(heating, crate::tpm::heating::py::thermal_irradiance);
+    pyadd_f!(heating, crate::tpm::heating::py::self_heating);
+    pyadd_f!(heating, crate::tpm::heating::py::view_factor_matrix_bodies);
+    pyadd_f!(heating, crate::tpm::heating::py::mutual_heating);
+    pyadd_f!(heating, crate::tpm::heating::py::mesh_hash);
+    tpm.add_submodule(&heating)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.heating", heating)?;
+
+    let illumination = PyModule::new(tpm.py(), "illumination")?;
+    pyadd_f!(illumination, crate::tpm::illumination::py::illumination);
+    pyadd_f!(illumination, crate::tpm::illumination::py::visible_sun_fraction);
+    tpm.add_submodule(&illumination)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.illumination", illumination)?;
+
+    let planetshine = PyModule::new(tpm.py(), "planetshine")?;
+    pyadd_f!(planetshine, crate::tpm::planetshine::py::planetshine);
+    tpm.add_submodule(&planetshine)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.planetshine", planetshine)?;
+
+    let recoil = PyModule::new(tpm.py(), "recoil")?;
+    pyadd_f!(recoil, crate::tpm::recoil::py::recoil);
+    pyadd_f!(recoil, crate::tpm::recoil::py::recoil_mean);
+    pyadd_f!(recoil, crate::tpm::recoil::py::yarkovsky_drift);
+    pyadd_f!(recoil, crate::tpm::recoil::py::yorp_acceleration);
+    tpm.add_submodule(&recoil)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.recoil", recoil)?;
+
+    let response = PyModule::new(tpm.py(), "response")?;
+    response.add_class::<tpm::response::SpectralResponse>()?;
+    response.add_class::<tpm::response::BandPlanck>()?;
+    tpm.add_submodule(&response)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.response", response)?;
+
+    let simple = PyModule::new(tpm.py(), "simple")?;
+    pyadd_f!(simple, crate::tpm::simple::bond_albedo);
+    pyadd_f!(simple, crate::tpm::simple::diameter);
+    pyadd_f!(simple, crate::tpm::simple::py::subsolar_temperature);
+    pyadd_f!(simple, crate::tpm::simple::py::flux);
+    pyadd_f!(simple, crate::tpm::simple::py::flux_ellipsoid);
+    tpm.add_submodule(&simple)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.tpm.simple", simple)?;
+
     let app = PyModule::new(m.py(), "app")?;
     m.add_submodule(&app)?;
     py.import("sys")?
@@ -264,23 +374,26 @@ fn python_module(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
         .getattr("modules")?
         .set_item("kalast._rs.app.simulation", &simulation)?;
 
-    // let routines = PyModule::new(m.py(), "routines")?;
-    // m.add_submodule(&routines)?;
-    // py.import("sys")?
-    //     .getattr("modules")?
-    //     .set_item("kalast._rs.routines", &routines)?;
-
-    // let setup = PyModule::new(routines.py(), "setup")?;
-    // setup.add_class::<routines::setup::ProgressDebug>()?;
-    // setup.add_class::<routines::setup::Time>()?;
-    // setup.add_class::<crate::routines::setup::SkinDepthParams>()?;
-    // setup.add_class::<routines::setup::BodyDataMap>()?;
-    // setup.add_class::<routines::setup::Body>()?;
-    // setup.add_class::<routines::setup::Setup>()?;
-    // routines.add_submodule(&routines)?;
-    // py.import("sys")?
-    //     .getattr("modules")?
-    //     .set_item("kalast._rs.routines.setup", setup)?;
+    let routines = PyModule::new(m.py(), "routines")?;
+    m.add_submodule(&routines)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.routines", &routines)?;
+
+    let setup = PyModule::new(routines.py(), "setup")?;
+    setup.add_class::<routines::setup::ProgressDebug>()?;
+    setup.add_class::<routines::setup::Time>()?;
+    setup.add_class::<crate::routines::setup::SkinDepthParams>()?;
+    setup.add_class::<crate::routines::setup::SetupColumn>()?;
+    setup.add_class::<crate::routines::setup::Record>()?;
+    setup.add_class::<routines::setup::BodyDataMap>()?;
+    setup.add_class::<routines::setup::BodyRecord>()?;
+    setup.add_class::<routines::setup::Body>()?;
+    setup.add_class::<routines::setup::Setup>()?;
+    routines.add_submodule(&setup)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("kalast._rs.routines.setup", setup)?;
 
     Ok(())
 }
@@ -1,20 +1,35 @@
 use std::{cell::RefCell, rc::Rc};
 
-use ndarray::Array1;
-use numpy::{PyArrayMethods, ToPyArray, ndarray};
-use pyo3::prelude::*;
+use ndarray::{Array1, Array2};
+use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2, ToPyArray, ndarray};
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
 
 use crate::{
-    Float,
-    //Mat4,
-    py::tpm::properties::Properties,
-    routines::setup::{
-        // Body as RsBody, Interior as RsInterior,
-        ProgressDebug as RsProgressDebug,
-        Time as RsTime,
+    Float, Vec3,
+    py::{entity::Body as EntityBody, mesh::Mesh, tpm::properties::Properties},
+    routines::{
+        setup::{
+            Body as RsBody, BodyDataMap as RsBodyDataMap, Checkpoint, HostPlanet, Illumination,
+            InitialTemperature, Interior as RsInterior, MutualHeating,
+            ProgressDebug as RsProgressDebug, Record, SelfHeating, Setup as RsSetup, SetupColumn,
+            Spinup, Substeps as RsSubsteps, Time as RsTime,
+        },
+        state::BodyRecord as RsBodyRecord,
+    },
+    tpm::{
+        planetshine::PlanetTemperature,
+        routine::{
+            Scheme, SurfaceSolver,
+            py::{Fallback, surface_solver},
+        },
+        viewfactors::ViewFactorMethod,
     },
 };
 
+fn runtime_error(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:#}", e))
+}
+
 #[pyclass(from_py_object, unsendable)]
 #[derive(Clone)]
 pub struct ProgressDebug {
@@ -86,6 +101,7 @@ impl Time {
                 dt,
                 duration_total,
                 duration_record,
+                substeps: None,
             })),
         }
     }
@@ -120,6 +136,19 @@ impl Time {
         self.inner.borrow_mut().duration_record = v;
     }
 
+    fn set_step_fixed(&self) {
+        self.inner.borrow_mut().substeps = None;
+    }
+
+    #[pyo3(signature = (stability=0.5, flux_change=10.0, max=1000))]
+    fn set_step_adaptive(&self, stability: Float, flux_change: Float, max: usize) {
+        self.inner.borrow_mut().substeps = Some(RsSubsteps {
+            stability,
+            flux_change,
+            max,
+        });
+    }
+
     pub fn __repr__(&self) -> String {
         format!("{:?}", self.inner.borrow())
     }
@@ -128,66 +157,61 @@ impl Time {
 #[pyclass(from_py_object, unsendable)]
 #[derive(Clone)]
 pub struct Body {
-    // pub inner: Rc<RefCell<RsBody>>,
-    // pub surface: Rc<RefCell<RsSurface>>,
-    // pub interior: Rc<RefCell<RsInterior>>,
+    pub inner: Rc<RefCell<RsBody>>,
 }
 
 #[pymethods]
 impl Body {
     #[new]
-    #[pyo3(signature = ())]
-    // #[pyo3(signature = (mesh=None, state=[[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]], spin_period=0.0, spin_axis=[0.0, 0.0, 1.0], orbit_period=0.0, orbit_axis=[0.0, 0.0, 1.0]))]
-    pub fn new(// mesh: Option<crate::py::mesh::Mesh>,
-        // state: [[Float; 4]; 4],
-        // spin_period: Float,
-        // spin_axis: [Float; 3],
-        // orbit_period: Float,
-        // orbit_axis: [Float; 3],
-    ) -> Self {
-        Self {
-            // inner: Rc::new(RefCell::new(RsBody {
-            //     mesh: mesh.unwrap_or(crate::py::mesh::Mesh::empty()),
-            //     interior: RsInterior::Column(vec![]),
-            //     state: Mat4::from_cols_array_2d(&state),
-            //     spin_period,
-            //     spin_axis: spin_axis.into(),
-            //     orbit_period,
-            //     orbit_axis: orbit_axis.into(),
-            // })),
+    #[pyo3(signature = (mesh=None, state=None, spin_period=0.0, spin_axis=[0.0, 0.0, 1.0], orbit_period=0.0, orbit_axis=[0.0, 0.0, 1.0]))]
+    pub fn new(
+        mesh: Option<PyRef<'_, Mesh>>,
+        state: Option<PyReadonlyArray2<'_, Float>>,
+        spin_period: Float,
+        spin_axis: [Float; 3],
+        orbit_period: Float,
+        orbit_axis: [Float; 3],
+    ) -> PyResult<Self> {
+        // state: model matrix at start (4, 4), identity if None
+        let mut body = RsBody::new();
+        if let Some(mesh) = mesh {
+            body.mesh = mesh.inner.borrow().clone();
+        }
+        if let Some(state) = state {
+            body.state = crate::py::util::mat4(&state)?;
         }
+        body.spin_period = spin_period;
+        body.spin_axis = Vec3::from_array(spin_axis);
+        body.orbit_period = orbit_period;
+        body.orbit_axis = Vec3::from_array(orbit_axis);
+        Ok(Self {
+            inner: Rc::new(RefCell::new(body)),
+        })
     }
 
-    /*
+    // Copy of the mesh of the body.
     #[getter]
-    fn mesh<'py>(&'py self, py: Python<'py>) -> PyResult<Py<crate::mesh::Mesh>> {
-        let mesh = crate::mesh::Mesh {
-            inner: Rc::clone(&self.mesh),
-        };
-        Py::new(py, mesh)
+    fn mesh(&self) -> Mesh {
+        Mesh {
+            inner: Rc::new(RefCell::new(self.inner.borrow().mesh.clone())),
+        }
     }
 
     #[setter]
-    fn set_mesh(&mut self, mesh: PyRef<crate::mesh::Mesh>) {
-        self.inner.borrow_mut().mesh = Rc::clone(&mesh);
+    fn set_mesh(&self, mesh: PyRef<'_, Mesh>) {
+        self.inner.borrow_mut().mesh = mesh.inner.borrow().clone();
     }
-    */
 
-    /*
-    // Getter that allows numpy.ndarray read and write.
     #[getter]
-    fn state<'py>(slf: Bound<'py, Self>) -> Bound<'py, numpy::PyArray2<Float>> {
-        let inner = &slf.borrow().inner;
-        let matref = &inner.borrow().state;
-        let arr = ndarray::ArrayView::from(matref.as_ref());
-        let arr2d = arr.into_shape_with_order((4, 4)).unwrap();
-        unsafe { numpy::PyArray2::borrow_from_array(&arr2d, slf.into_any()) }
+    fn state<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Float>> {
+        let state = self.inner.borrow().state.transpose().to_cols_array();
+        PyArray1::from_slice(py, &state).reshape([4, 4]).unwrap()
     }
 
-    // Setter that allows shorthand operators.
     #[setter]
-    fn set_state(&self, state: [[Float; 4]; 4]) {
-        self.inner.borrow_mut().state = Mat4::from_cols_array_2d(&state);
+    fn set_state(&self, state: PyReadonlyArray2<'_, Float>) -> PyResult<()> {
+        self.inner.borrow_mut().state = crate::py::util::mat4(&state)?;
+        Ok(())
     }
 
     #[getter]
@@ -200,16 +224,11 @@ impl Body {
         self.inner.borrow_mut().spin_period = period;
     }
 
-    // Getter that allows numpy.ndarray read and write.
     #[getter]
-    fn spin_axis<'py>(slf: Bound<'py, Self>) -> Bound<'py, numpy::PyArray1<Float>> {
-        let inner = &slf.borrow().inner;
-        let slice = &inner.borrow().spin_axis;
-        let arr = ndarray::ArrayView1::from(slice.as_ref());
-        unsafe { numpy::PyArray1::borrow_from_array(&arr, slf.into_any()) }
+    fn spin_axis(&self) -> [Float; 3] {
+        self.inner.borrow().spin_axis.to_array()
     }
 
-    // Setter that allows shorthand operators.
     #[setter]
     fn set_spin_axis(&self, axis: [Float; 3]) {
         self.inner.borrow_mut().spin_axis = axis.into();
@@ -225,59 +244,98 @@ impl Body {
         self.inner.borrow_mut().orbit_period = period;
     }
 
-    // Getter that allows numpy.ndarray read and write.
     #[getter]
-    fn orbit_axis<'py>(slf: Bound<'py, Self>) -> Bound<'py, numpy::PyArray1<Float>> {
-        let inner = &slf.borrow().inner;
-        let slice = &inner.borrow().orbit_axis;
-        let arr = ndarray::ArrayView1::from(slice.as_ref());
-        unsafe { numpy::PyArray1::borrow_from_array(&arr, slf.into_any()) }
+    fn orbit_axis(&self) -> [Float; 3] {
+        self.inner.borrow().orbit_axis.to_array()
     }
 
-    // Setter that allows shorthand operators.
     #[setter]
     fn set_orbit_axis(&self, axis: [Float; 3]) {
         self.inner.borrow_mut().orbit_axis = axis.into();
     }
 
+    // Depth of the nodes of the columns (m), the same for all facets.
+    fn set_interior_column(&self, z: Vec<Float>) {
+        self.inner.borrow_mut().interior = RsInterior::Column(z);
+    }
+
+    // Columns made from the thermal properties of each facet.
+    fn set_interior_setup(&self, setup: SetupColumn) {
+        self.inner.borrow_mut().interior = RsInterior::SetupColumn(setup);
+    }
+
+    // Scattered sunlight and thermal emission between facets of the body, see
+    // routines::setup::SelfHeating.
+    // cache: directory of the saved view factors, computed at each preparation if None
+    // rays: rays traced from each facet for the Monte-Carlo estimator, analytic if None
+    // seed: seed of the Monte-Carlo estimator
+    #[pyo3(signature = (occlusion=true, scattering=1, thermal=true, cutoff=0.0, cache=None, rays=None, seed=0))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_self_heating(
+        &self,
+        occlusion: bool,
+        scattering: usize,
+        thermal: bool,
+        cutoff: Float,
+        cache: Option<std::path::PathBuf>,
+        rays: Option<usize>,
+        seed: u64,
+    ) {
+        self.inner.borrow_mut().self_heating = Some(SelfHeating {
+            occlusion,
+            scattering,
+            thermal,
+            cutoff,
+            method: ViewFactorMethod::from_rays(rays, seed),
+            cache,
+        });
+    }
+
+    fn set_self_heating_none(&self) {
+        self.inner.borrow_mut().self_heating = None;
+    }
+
     pub fn __repr__(&self) -> String {
         format!("{:?}", self.inner.borrow())
     }
-    */
 }
 
-/*
 impl std::fmt::Debug for Body {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.inner.borrow())
     }
 }
-*/
 
 #[pyclass(unsendable)]
 pub struct BodyDataMap {
     temperatures: Vec<Array1<Float>>,
+    initial: InitialTemperature,
     thermal_properties_all: usize,
     thermal_properties_map: Vec<(usize, usize)>,
-    // TODO: ADD RECORD
+
+    #[pyo3(get, set)]
+    record: Record,
 }
 
 #[pymethods]
 impl BodyDataMap {
     #[new]
-    #[pyo3(signature = (temperatures, thermal_properties_all, thermal_properties_map))]
+    #[pyo3(signature = (temperatures=vec![], thermal_properties_all=0, thermal_properties_map=vec![], record=None))]
     fn new(
         temperatures: Vec<numpy::PyReadonlyArray1<Float>>,
         thermal_properties_all: usize,
         thermal_properties_map: Vec<(usize, usize)>,
+        record: Option<Record>,
     ) -> Self {
         Self {
             temperatures: temperatures
                 .iter()
                 .map(|a| a.to_owned_array())
                 .collect::<Vec<_>>(),
+            initial: InitialTemperature::Effective,
             thermal_properties_all,
             thermal_properties_map,
+            record: record.unwrap_or_else(Record::new),
         }
     }
 
@@ -295,6 +353,17 @@ impl BodyDataMap {
         Ok(())
     }
 
+    // Effective temperature at all depths when no temperatures are given.
+    fn set_initial_effective(&mut self) {
+        self.initial = InitialTemperature::Effective;
+    }
+
+    // Profile from the absorbed flux averaged over n samples of a rotation when no temperatures
+    // are given.
+    fn set_initial_diurnal(&mut self, n: usize) {
+        self.initial = InitialTemperature::Diurnal(n);
+    }
+
     #[getter]
     fn thermal_properties_all(&self) -> usize {
         self.thermal_properties_all
@@ -317,12 +386,81 @@ impl BodyDataMap {
 
     pub fn __repr__(&self) -> String {
         format!(
-            "BodyDataMap(temperatures={:?}, thermal_properties_all={}, thermal_properties_map={:?})",
-            &self.temperatures, self.thermal_properties_all, self.thermal_properties_map,
+            "BodyDataMap(temperatures={:?}, initial={:?}, thermal_properties_all={}, thermal_properties_map={:?}, record={:?})",
+            &self.temperatures,
+            self.initial,
+            self.thermal_properties_all,
+            self.thermal_properties_map,
+            self.record,
         )
     }
 }
 
+impl BodyDataMap {
+    fn to_rs(&self) -> RsBodyDataMap {
+        RsBodyDataMap {
+            temperatures: self.temperatures.clone(),
+            initial: self.initial.clone(),
+            thermal_properties_all: self.thermal_properties_all,
+            thermal_properties_map: self.thermal_properties_map.clone(),
+            record: self.record.clone(),
+        }
+    }
+}
+
+// Records of a body returned by Setup.run, see routines::state::BodyRecord.
+#[pyclass(unsendable)]
+pub struct BodyRecord {
+    inner: RsBodyRecord,
+}
+
+#[pymethods]
+impl BodyRecord {
+    #[getter]
+    fn facets(&self) -> Vec<usize> {
+        self.inner.facets.clone()
+    }
+
+    #[getter]
+    fn time<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        self.inner.time.to_pyarray(py)
+    }
+
+    #[getter]
+    fn temperature_surface<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Float>> {
+        self.inner.temperature_surface.to_pyarray(py)
+    }
+
+    #[getter]
+    fn flux_surface<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Float>> {
+        self.inner.flux_surface.to_pyarray(py)
+    }
+
+    #[getter]
+    fn flux_mutual<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Float>> {
+        self.inner.flux_mutual.to_pyarray(py)
+    }
+
+    #[getter]
+    fn interior_time_indices(&self) -> Vec<usize> {
+        self.inner.interior_time_indices.clone()
+    }
+
+    // (interior time index)(facet) columns of temperatures (K)
+    #[getter]
+    fn temperature_interior<'py>(&self, py: Python<'py>) -> Vec<Vec<Bound<'py, PyArray1<Float>>>> {
+        self.inner
+            .temperature_interior
+            .iter()
+            .map(|facets| facets.iter().map(|t| t.to_pyarray(py)).collect())
+            .collect()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("{:?}", self.inner)
+    }
+}
+
 #[pyclass(unsendable)]
 pub struct Setup {
     pub sun_position: RefCell<[Float; 3]>,
@@ -343,6 +481,34 @@ pub struct Setup {
     pub progress_debug: Rc<RefCell<RsProgressDebug>>,
 
     pub time: Rc<RefCell<RsTime>>,
+
+    pub scheme: Scheme,
+
+    // tolerance of the surface temperature and fallback when it does not converge
+    pub solver: SurfaceSolver,
+
+    // rotations repeated before the start of the simulation until convergence
+    pub spinup: Option<Spinup>,
+
+    // shadows from rays cast towards the Sun, all facets facing the Sun are lit if None
+    pub illumination: Option<Illumination>,
+
+    // scattered sunlight and thermal emission between the bodies
+    pub mutual_heating: Option<MutualHeating>,
+
+    // large planet the bodies orbit
+    pub planet: Option<HostPlanet>,
+
+    // state written to a file periodically during the simulation
+    pub checkpoint: Option<Checkpoint>,
+
+    // threads updating the facets, all available if 0
+    #[pyo3(get, set)]
+    pub threads: usize,
+
+    // simulation made by prepare from the fields above, later changes are ignored until prepare
+    // is called again
+    pub inner: Option<RsSetup>,
 }
 
 #[pymethods]
@@ -356,6 +522,15 @@ impl Setup {
             bodies_data_map: vec![],
             progress_debug: Rc::new(RefCell::new(RsProgressDebug::new())),
             time: Rc::new(RefCell::new(RsTime::new())),
+            scheme: Scheme::Explicit,
+            solver: SurfaceSolver::default(),
+            spinup: None,
+            illumination: None,
+            mutual_heating: None,
+            planet: None,
+            checkpoint: None,
+            threads: 0,
+            inner: None,
         }
     }
 
@@ -366,9 +541,7 @@ impl Setup {
 
     #[setter]
     fn set_sun_position(&self, v: numpy::PyReadonlyArray1<Float>) -> PyResult<()> {
-        self.sun_position
-            .borrow_mut()
-            .copy_from_slice(v.as_slice().unwrap());
+        *self.sun_position.borrow_mut() = crate::py::util::vec3(&v)?.to_array();
         Ok(())
     }
 
@@ -398,16 +571,344 @@ impl Setup {
         self.time = Rc::clone(&time.inner);
     }
 
+    fn set_scheme_explicit(&mut self) {
+        self.scheme = Scheme::Explicit;
+    }
+
+    // theta: 0.5 Crank-Nicolson, 1 backward Euler
+    #[pyo3(signature = (theta=0.5))]
+    fn set_scheme_implicit(&mut self, theta: Float) {
+        self.scheme = Scheme::Implicit(theta);
+    }
+
+    // tolerance: convergence on surface temperature (K), default if None
+    // fallback: raise, reuse, or bounds (min, max) of the clamped estimate (K), raise if None
+    #[pyo3(signature = (tolerance=None, fallback=None))]
+    fn set_solver(&mut self, tolerance: Option<Float>, fallback: Option<Fallback>) -> PyResult<()> {
+        self.solver = surface_solver(tolerance, fallback)?;
+        Ok(())
+    }
+
+    // tolerance: largest change of temperatures between the ends of consecutive rotations (K)
+    #[pyo3(signature = (tolerance=0.1, max_rotations=100))]
+    fn set_spinup(&mut self, tolerance: Float, max_rotations: usize) {
+        self.spinup = Some(Spinup {
+            tolerance,
+            max_rotations,
+        });
+    }
+
+    fn set_spinup_none(&mut self) {
+        self.spinup = None;
+    }
+
+    // cast: shadows cast by the other bodies, only self-shadowing otherwise
+    // subdivisions: subdivisions of the edges of the facets for partial shadows
+    // sun_samples: points of the disk of the Sun for penumbras, 1 for a point Sun
+    // ellipsoids: bodies casting shadows that are not simulated, ellipsoids of their radii
+    // trans_ellipsoids: model matrix of each ellipsoid (4, 4), fixed
+    #[pyo3(signature = (cast=true, subdivisions=1, sun_samples=1, ellipsoids=vec![], trans_ellipsoids=vec![]))]
+    fn set_illumination(
+        &mut self,
+        cast: bool,
+        subdivisions: usize,
+        sun_samples: usize,
+        ellipsoids: Vec<EntityBody>,
+        trans_ellipsoids: Vec<PyReadonlyArray2<'_, Float>>,
+    ) -> PyResult<()> {
+        if ellipsoids.len() != trans_ellipsoids.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Expected one transformation per ellipsoid, got {} for {} ellipsoids",
+                trans_ellipsoids.len(),
+                ellipsoids.len()
+            )));
+        }
+        let ellipsoids = ellipsoids
+            .iter()
+            .zip(&trans_ellipsoids)
+            .map(|(body, trans)| Ok((body.inner.borrow().clone(), crate::py::util::mat4(trans)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.illumination = Some(Illumination {
+            cast,
+            subdivisions,
+            sun_samples,
+            ellipsoids,
+        });
+        Ok(())
+    }
+
+    fn set_illumination_none(&mut self) {
+        self.illumination = None;
+    }
+
+    // scattering: sunlight scattered once by the other bodies
+    // thermal: thermal emission of the other bodies
+    // occlusion: pairs of facets hidden by another facet of either body are removed
+    // cutoff: pairs of facets with view factors not above are removed
+    #[pyo3(signature = (scattering=true, thermal=true, occlusion=true, cutoff=0.0))]
+    fn set_mutual_heating(
+        &mut self,
+        scattering: bool,
+        thermal: bool,
+        occlusion: bool,
+        cutoff: Float,
+    ) {
+        self.mutual_heating = Some(MutualHeating {
+            scattering,
+            thermal,
+            occlusion,
+            cutoff,
+        });
+    }
+
+    fn set_mutual_heating_none(&mut self) {
+        self.mutual_heating = None;
+    }
+
+    // body: entity of the radii of the planet
+    // state: model matrix of the planet (4, 4), fixed, identity if None
+    // temperature: uniform temperature of the planet (K), radiative equilibrium if None
+    // night: lowest temperature of the planet at radiative equilibrium (K)
+    // resolution: bands of latitude of the surface elements
+    // cast: shadows cast by the planet on the bodies, needs illumination
+    #[pyo3(signature = (body, state=None, albedo=0.25, emissivity=0.95, temperature=None, night=0.0, resolution=32, cast=true))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_planet(
+        &mut self,
+        body: EntityBody,
+        state: Option<PyReadonlyArray2<'_, Float>>,
+        albedo: Float,
+        emissivity: Float,
+        temperature: Option<Float>,
+        night: Float,
+        resolution: usize,
+        cast: bool,
+    ) -> PyResult<()> {
+        let mut planet = HostPlanet::new(body.inner.borrow().clone());
+        if let Some(state) = state {
+            planet.state = crate::py::util::mat4(&state)?;
+        }
+        planet.albedo = albedo;
+        planet.emissivity = emissivity;
+        planet.temperature = match temperature {
+            Some(t) => PlanetTemperature::Uniform(t),
+            None => PlanetTemperature::Equilibrium { night },
+        };
+        planet.resolution = resolution;
+        planet.cast = cast;
+        self.planet = Some(planet);
+        Ok(())
+    }
+
+    fn set_planet_none(&mut self) {
+        self.planet = None;
+    }
+
+    // Create the simulation from the setup, see routines::setup::Setup::prepare.
+    fn prepare(&mut self, py: Python<'_>) -> PyResult<()> {
+        let mut setup = self.build(py);
+        setup.prepare().map_err(runtime_error)?;
+        self.inner = Some(setup);
+        Ok(())
+    }
+
+    // Create the simulation from the setup with the temperatures of the columns of a
+    // checkpoint, see routines::setup::Setup::prepare_from_checkpoint.
+    fn prepare_from_checkpoint(
+        &mut self,
+        py: Python<'_>,
+        path: std::path::PathBuf,
+    ) -> PyResult<()> {
+        let mut setup = self.build(py);
+        setup.prepare_from_checkpoint(path).map_err(runtime_error)?;
+        self.inner = Some(setup);
+        Ok(())
+    }
+
+    // Write the state of the simulation, see routines::setup::Setup::save_checkpoint.
+    fn save_checkpoint(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("Nothing to checkpoint before prepare"))?
+            .save_checkpoint(path)
+            .map_err(runtime_error)
+    }
+
+    // Create the simulation from the setup and continue it from a checkpoint written with the
+    // same setup, see routines::setup::Setup::load_checkpoint.
+    fn load_checkpoint(&mut self, py: Python<'_>, path: std::path::PathBuf) -> PyResult<()> {
+        let mut setup = self.build(py);
+        setup.load_checkpoint(path).map_err(runtime_error)?;
+        self.inner = Some(setup);
+        Ok(())
+    }
+
+    // every: steps between two checkpoints
+    // path: file overwritten at each checkpoint
+    fn set_checkpoint(&mut self, every: usize, path: std::path::PathBuf) {
+        self.checkpoint = Some(Checkpoint::new(every, path));
+    }
+
+    fn set_checkpoint_none(&mut self) {
+        self.checkpoint = None;
+    }
+
+    // Advance of one time step, prepared first if needed. Returns False if the simulation was
+    // already finished.
+    fn step(&mut self, py: Python<'_>) -> PyResult<bool> {
+        if self.inner.is_none() {
+            self.prepare(py)?;
+        }
+        self.inner.as_mut().unwrap().step().map_err(runtime_error)
+    }
+
+    // Run until the total duration and return the records of each body.
+    fn run(&mut self, py: Python<'_>) -> PyResult<Vec<BodyRecord>> {
+        if self.inner.is_none() {
+            self.prepare(py)?;
+        }
+        let records = self.inner.as_mut().unwrap().run().map_err(runtime_error)?;
+        Ok(records
+            .into_iter()
+            .map(|inner| BodyRecord { inner })
+            .collect())
+    }
+
+    // Time since start of the simulation (s), None before prepare.
+    #[getter]
+    fn elapsed(&self) -> Option<Float> {
+        self.inner.as_ref()?.state.as_ref().map(|s| s.time)
+    }
+
+    // Current surface temperature of the facets of a body (K).
+    fn temperature_surface<'py>(
+        &self,
+        py: Python<'py>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let body_state = self
+            .inner
+            .as_ref()
+            .and_then(|s| s.state.as_ref())
+            .and_then(|s| s.bodies.get(body))
+            .ok_or_else(|| PyRuntimeError::new_err(format!("No state of body {}", body)))?;
+        let t = &body_state.interior.t;
+        Ok(Array1::from_shape_fn(t.nrows(), |ii| t[[ii, 0]]).to_pyarray(py))
+    }
+
+    // Views without copy of the interior of a body (facet, depth), the nodes past the column of
+    // a facet repeat its last node. The views follow the simulation as it steps and are no
+    // longer valid once the simulation is created again (prepare, prepare_from_checkpoint or
+    // load_checkpoint).
+
+    // temperature (K)
+    fn interior_t<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        Self::interior_view(slf, body, |i| &i.t)
+    }
+
+    // depth (m)
+    fn interior_d<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        Self::interior_view(slf, body, |i| &i.d)
+    }
+
+    // density (kg/m3)
+    fn interior_p<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        Self::interior_view(slf, body, |i| &i.p)
+    }
+
+    // heat capacity (J/kg/K)
+    fn interior_c<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        Self::interior_view(slf, body, |i| &i.c)
+    }
+
+    // thermal conductivity (W/m/K)
+    fn interior_k<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        Self::interior_view(slf, body, |i| &i.k)
+    }
+
     pub fn __repr__(&self) -> String {
         format!(
-            // "Setup(sun_position={:?}, thermal_properties={:?}, bodies={:?}, bodies_data_map={:?}, progress_debug={:?}, time={:?})",
-            "Setup(sun_position={:?}, thermal_properties={:?}, bodies=???, bodies_data_map={:?}, progress_debug={:?}, time={:?})",
+            "Setup(sun_position={:?}, thermal_properties={:?}, bodies={:?}, bodies_data_map={:?}, progress_debug={:?}, time={:?}, scheme={:?}, solver={:?}, spinup={:?}, illumination={:?}, mutual_heating={:?}, planet={:?}, checkpoint={:?}, threads={})",
             self.sun_position.borrow(),
             self.thermal_properties,
-            // self.bodies,
+            self.bodies,
             self.bodies_data_map,
             self.progress_debug.borrow(),
-            self.time.borrow()
+            self.time.borrow(),
+            self.scheme,
+            self.solver,
+            self.spinup,
+            self.illumination,
+            self.mutual_heating,
+            self.planet,
+            self.checkpoint,
+            self.threads,
         )
     }
 }
+
+impl Setup {
+    // Simulation made from the fields, not prepared.
+    fn build(&self, py: Python<'_>) -> RsSetup {
+        let mut setup = RsSetup::new();
+        setup.sun_position = Vec3::from_array(*self.sun_position.borrow());
+        setup.thermal_properties = self
+            .thermal_properties
+            .iter()
+            .map(|p| p.inner.borrow().clone())
+            .collect();
+        setup.bodies = self
+            .bodies
+            .iter()
+            .map(|b| b.inner.borrow().clone())
+            .collect();
+        setup.bodies_data_map = self
+            .bodies_data_map
+            .iter()
+            .map(|d| d.borrow(py).to_rs())
+            .collect();
+        setup.progress_debug = self.progress_debug.borrow().clone();
+        setup.time = self.time.borrow().clone();
+        setup.scheme = self.scheme;
+        setup.solver = self.solver;
+        setup.spinup = self.spinup.clone();
+        setup.illumination = self.illumination.clone();
+        setup.mutual_heating = self.mutual_heating.clone();
+        setup.planet = self.planet.clone();
+        setup.checkpoint = self.checkpoint.clone();
+        setup.threads = self.threads;
+        setup
+    }
+
+    fn interior_view<'py>(
+        slf: Bound<'py, Self>,
+        body: usize,
+        get: fn(&crate::tpm::column::Interior) -> &Array2<Float>,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        let setup = slf.borrow();
+        let body_state = setup
+            .inner
+            .as_ref()
+            .and_then(|s| s.state.as_ref())
+            .and_then(|s| s.bodies.get(body))
+            .ok_or_else(|| PyRuntimeError::new_err(format!("No state of body {}", body)))?;
+        let arr = get(&body_state.interior).view();
+        // the array lives in the state owned by the Setup, kept alive by the view
+        Ok(unsafe { PyArray2::borrow_from_array(&arr, slf.clone().into_any()) })
+    }
+}
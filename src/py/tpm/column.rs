@@ -3,7 +3,10 @@ use std::{cell::RefCell, rc::Rc};
 use numpy::{PyArrayMethods, PyReadonlyArray1};
 use pyo3::prelude::*;
 
-use crate::{Float, tpm::column::Column as RsColumn};
+use crate::{
+    Float,
+    tpm::column::{Column as RsColumn, Interior as RsInterior},
+};
 
 #[pyclass(from_py_object, unsendable, dict)]
 #[derive(Clone)]
@@ -42,7 +45,25 @@ impl Column {
         let inner = &slf.borrow().inner;
         unsafe { numpy::PyArray1::borrow_from_array(&inner.borrow().d, slf.into_any()) }
     }
-    
+
+    #[getter]
+    fn p<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray1<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray1::borrow_from_array(&inner.borrow().p, slf.into_any()) }
+    }
+
+    #[getter]
+    fn c<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray1<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray1::borrow_from_array(&inner.borrow().c, slf.into_any()) }
+    }
+
+    #[getter]
+    fn k<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray1<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray1::borrow_from_array(&inner.borrow().k, slf.into_any()) }
+    }
+
     fn clone(&self) -> Self {
         Self { inner: Rc::new(RefCell::new(self.inner.borrow().clone())) }
     }
@@ -57,3 +78,74 @@ impl std::fmt::Debug for Column {
         write!(f, "{:?}", self.inner.borrow())
     }
 }
+
+#[pyclass(from_py_object, unsendable, dict)]
+#[derive(Clone)]
+pub struct Interior {
+    pub inner: Rc<RefCell<RsInterior>>,
+}
+
+#[pymethods]
+impl Interior {
+    #[new]
+    #[pyo3(signature = (columns))]
+    fn new(columns: Vec<Column>) -> Self {
+        let columns: Vec<RsColumn> = columns.iter().map(|c| c.inner.borrow().clone()).collect();
+        Self {
+            inner: Rc::new(RefCell::new(RsInterior::from_columns(&columns))),
+        }
+    }
+
+    #[getter]
+    fn t<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray2<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray2::borrow_from_array(&inner.borrow().t, slf.into_any()) }
+    }
+
+    #[getter]
+    fn d<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray2<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray2::borrow_from_array(&inner.borrow().d, slf.into_any()) }
+    }
+
+    #[getter]
+    fn p<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray2<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray2::borrow_from_array(&inner.borrow().p, slf.into_any()) }
+    }
+
+    #[getter]
+    fn c<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray2<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray2::borrow_from_array(&inner.borrow().c, slf.into_any()) }
+    }
+
+    #[getter]
+    fn k<'py>(slf: pyo3::Bound<'py, Self>) -> pyo3::Bound<'py, numpy::PyArray2<Float>> {
+        let inner = &slf.borrow().inner;
+        unsafe { numpy::PyArray2::borrow_from_array(&inner.borrow().k, slf.into_any()) }
+    }
+
+    #[getter]
+    fn sizes(&self) -> Vec<usize> {
+        self.inner.borrow().sizes.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.borrow().len()
+    }
+
+    fn clone(&self) -> Self {
+        Self { inner: Rc::new(RefCell::new(self.inner.borrow().clone())) }
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("{:?}", self.inner.borrow())
+    }
+}
+
+impl std::fmt::Debug for Interior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.inner.borrow())
+    }
+}
@@ -1,2 +1,4 @@
 pub mod properties;
-pub mod column;
\ No newline at end of file
+pub mod column;
+pub mod viewfactors;
+pub mod response;
\ No newline at end of file
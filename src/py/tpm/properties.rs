@@ -2,7 +2,10 @@ use std::{cell::RefCell, rc::Rc};
 
 use pyo3::prelude::*;
 
-use crate::{Float, tpm::properties::Properties as RsProperties};
+use crate::{
+    Float,
+    tpm::properties::{ConductivityModel, HeatCapacityModel, Properties as RsProperties},
+};
 
 #[pyclass(from_py_object, unsendable, dict)]
 #[derive(Clone)]
@@ -40,6 +43,8 @@ impl Properties {
                 thermal_inertia,
                 conductivity,
                 diffusivity,
+                conductivity_model: ConductivityModel::Constant,
+                heat_capacity_model: HeatCapacityModel::Constant,
             })),
         }
     }
@@ -114,6 +119,48 @@ impl Properties {
         self.inner.borrow_mut().diffusivity = v;
     }
 
+    // Radiative parameter of the conductivity model, None if constant.
+    #[getter]
+    fn conductivity_radiative(&self) -> Option<Float> {
+        match self.inner.borrow().conductivity_model {
+            ConductivityModel::Constant => None,
+            ConductivityModel::Radiative(r) => Some(r),
+        }
+    }
+
+    #[setter]
+    fn set_conductivity_radiative(&self, r: Option<Float>) {
+        self.inner.borrow_mut().conductivity_model =
+            r.map_or(ConductivityModel::Constant, ConductivityModel::Radiative);
+    }
+
+    // Polynomial coefficients of the heat capacity model, None if constant.
+    #[getter]
+    fn heat_capacity_polynomial(&self) -> Option<Vec<Float>> {
+        match &self.inner.borrow().heat_capacity_model {
+            HeatCapacityModel::Constant => None,
+            HeatCapacityModel::Polynomial(c) => Some(c.clone()),
+        }
+    }
+
+    #[setter]
+    fn set_heat_capacity_polynomial(&self, c: Option<Vec<Float>>) {
+        self.inner.borrow_mut().heat_capacity_model =
+            c.map_or(HeatCapacityModel::Constant, HeatCapacityModel::Polynomial);
+    }
+
+    pub fn conductivity_at(&self, t: Float) -> Float {
+        self.inner.borrow().conductivity_at(t)
+    }
+
+    pub fn heat_capacity_at(&self, t: Float) -> Float {
+        self.inner.borrow().heat_capacity_at(t)
+    }
+
+    pub fn diffusivity_at(&self, t: Float) -> Float {
+        self.inner.borrow().diffusivity_at(t)
+    }
+
     pub fn compute_thermal_inertia(&mut self) {
         self.inner.borrow_mut().compute_thermal_inertia();
     }
@@ -0,0 +1,163 @@
+use std::{cell::RefCell, rc::Rc};
+
+use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
+use pyo3::prelude::*;
+
+use crate::{
+    Float,
+    tpm::response::{BandPlanck as RsBandPlanck, SpectralResponse as RsSpectralResponse},
+};
+
+fn value_error(e: anyhow::Error) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!("{:#}", e))
+}
+
+#[pyclass(from_py_object, unsendable)]
+#[derive(Clone)]
+pub struct SpectralResponse {
+    pub inner: Rc<RefCell<RsSpectralResponse>>,
+}
+
+impl SpectralResponse {
+    pub fn from_inner(response: RsSpectralResponse) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(response)),
+        }
+    }
+}
+
+#[pymethods]
+impl SpectralResponse {
+    #[new]
+    fn new(name: &str, w: PyReadonlyArray1<Float>, r: PyReadonlyArray1<Float>) -> PyResult<Self> {
+        RsSpectralResponse::new(name, w.as_array().to_owned(), r.as_array().to_owned())
+            .map(Self::from_inner)
+            .map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn boxcar(name: &str, w_min: Float, w_max: Float) -> PyResult<Self> {
+        RsSpectralResponse::boxcar(name, w_min, w_max)
+            .map(Self::from_inner)
+            .map_err(value_error)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, name, unit=1e-6))]
+    fn load_csv(path: &str, name: &str, unit: Float) -> PyResult<Self> {
+        RsSpectralResponse::load_csv(path, name, unit)
+            .map(Self::from_inner)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.inner.borrow().name.clone()
+    }
+
+    #[getter]
+    fn w<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        self.inner.borrow().w.to_pyarray(py)
+    }
+
+    #[getter]
+    fn r<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        self.inner.borrow().r.to_pyarray(py)
+    }
+
+    fn at(&self, w: Float) -> Float {
+        self.inner.borrow().at(w)
+    }
+
+    fn resample(&self, w: PyReadonlyArray1<Float>) -> PyResult<Self> {
+        self.inner
+            .borrow()
+            .resample(w.as_array())
+            .map(Self::from_inner)
+            .map_err(value_error)
+    }
+
+    fn resample_uniform(&self, n: usize) -> PyResult<Self> {
+        self.inner
+            .borrow()
+            .resample_uniform(n)
+            .map(Self::from_inner)
+            .map_err(value_error)
+    }
+
+    fn normalized(&self) -> Self {
+        Self::from_inner(self.inner.borrow().normalized())
+    }
+
+    fn width(&self) -> Float {
+        self.inner.borrow().width()
+    }
+
+    fn effective_wavelength(&self) -> Float {
+        self.inner.borrow().effective_wavelength()
+    }
+
+    fn band_radiance(&self, t: Float) -> Float {
+        self.inner.borrow().band_radiance(t)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner.borrow())
+    }
+}
+
+#[pyclass(from_py_object, unsendable)]
+#[derive(Clone)]
+pub struct BandPlanck {
+    pub inner: Rc<RefCell<RsBandPlanck>>,
+}
+
+#[pymethods]
+impl BandPlanck {
+    #[new]
+    #[pyo3(signature = (response, t_min=10.0, t_max=1000.0, n=4096))]
+    fn new(
+        response: PyRef<'_, SpectralResponse>,
+        t_min: Float,
+        t_max: Float,
+        n: usize,
+    ) -> PyResult<Self> {
+        let table = RsBandPlanck::new(&response.inner.borrow(), t_min, t_max, n);
+        Ok(Self {
+            inner: Rc::new(RefCell::new(table.map_err(value_error)?)),
+        })
+    }
+
+    fn radiance(&self, t: Float) -> Float {
+        self.inner.borrow().radiance(t)
+    }
+
+    fn temperature(&self, radiance: Float) -> Float {
+        self.inner.borrow().temperature(radiance)
+    }
+
+    fn radiance_array<'py>(
+        &self,
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        let lut = self.inner.borrow();
+        t.as_array().mapv(|t| lut.radiance(t)).to_pyarray(py)
+    }
+
+    fn temperature_array<'py>(
+        &self,
+        py: Python<'py>,
+        radiance: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        let lut = self.inner.borrow();
+        radiance
+            .as_array()
+            .mapv(|l| lut.temperature(l))
+            .to_pyarray(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner.borrow())
+    }
+}
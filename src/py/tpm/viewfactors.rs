@@ -0,0 +1,74 @@
+use std::{cell::RefCell, rc::Rc};
+
+use numpy::{PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2, ToPyArray};
+use pyo3::prelude::*;
+
+use crate::{Float, tpm::viewfactors::ViewFactors as RsViewFactors};
+
+#[pyclass(from_py_object, unsendable)]
+#[derive(Clone)]
+pub struct ViewFactors {
+    pub inner: Rc<RefCell<RsViewFactors>>,
+}
+
+impl ViewFactors {
+    pub fn from_inner(vf: RsViewFactors) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(vf)),
+        }
+    }
+}
+
+#[pymethods]
+impl ViewFactors {
+    #[new]
+    #[pyo3(signature = (a, cutoff=0.0))]
+    fn new(a: PyReadonlyArray2<Float>, cutoff: Float) -> Self {
+        Self::from_inner(RsViewFactors::from_dense(a.as_array(), cutoff))
+    }
+
+    #[staticmethod]
+    fn load(path: &str, key: u64) -> PyResult<Self> {
+        RsViewFactors::load(path, key)
+            .map(Self::from_inner)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))
+    }
+
+    fn save(&self, path: &str, key: u64) -> PyResult<()> {
+        self.inner
+            .borrow()
+            .save(path, key)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))
+    }
+
+    #[getter]
+    fn nrows(&self) -> usize {
+        self.inner.borrow().nrows
+    }
+
+    #[getter]
+    fn ncols(&self) -> usize {
+        self.inner.borrow().ncols
+    }
+
+    #[getter]
+    fn nnz(&self) -> usize {
+        self.inner.borrow().nnz()
+    }
+
+    fn to_dense<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Float>> {
+        self.inner.borrow().to_dense().to_pyarray(py)
+    }
+
+    fn dot<'py>(
+        &self,
+        py: Python<'py>,
+        x: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        self.inner.borrow().dot(x.as_array()).to_pyarray(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner.borrow())
+    }
+}
@@ -1,7 +1,10 @@
 use std::sync::Mutex;
 
+use numpy::{PyReadonlyArray1, PyReadonlyArray2};
 use once_cell::sync::Lazy;
-use pyo3::prelude::*;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{Float, Mat4, Vec3};
 
 pub fn isize_to_usize(mut index: isize, n: usize) -> PyResult<usize> {
     if index < 0 {
@@ -14,6 +17,31 @@ pub fn isize_to_usize(mut index: isize, n: usize) -> PyResult<usize> {
     Ok(index)
 }
 
+// Matrix from an array of shape (4, 4) in row-major order, e.g. a model matrix.
+pub fn mat4(x: &PyReadonlyArray2<'_, Float>) -> PyResult<Mat4> {
+    let x = x.as_array();
+    if x.shape() != [4, 4] {
+        return Err(PyValueError::new_err(format!(
+            "Expected a matrix of shape (4, 4), got {:?}",
+            x.shape()
+        )));
+    }
+    let rows: Vec<_> = x.iter().copied().collect();
+    Ok(Mat4::from_cols_slice(&rows).transpose())
+}
+
+// Vector from an array of shape (3,).
+pub fn vec3(x: &PyReadonlyArray1<'_, Float>) -> PyResult<Vec3> {
+    let x = x.as_array();
+    if x.len() != 3 {
+        return Err(PyValueError::new_err(format!(
+            "Expected a vector of shape (3,), got {:?}",
+            x.shape()
+        )));
+    }
+    Ok(Vec3::new(x[0], x[1], x[2]))
+}
+
 #[macro_export]
 macro_rules! pyadd_c {
     ($m:expr, $p:ident $(::$c:tt)+) => {
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashMap,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, anyhow};
+use ndarray::{Array1, Array2};
+
+use super::state::{BodyRecord, BodyState, Grid, State};
+use crate::{Float, Mat4, tpm::column::Interior};
+
+// Binary checkpoint of the simulation state, little-endian:
+//
+// magic (8 bytes), version (u32), size of Float in bytes (u8), time step (Float),
+// iteration, iteration_total, iteration_record (u64), time, progress (Float),
+// spinup_rotations (u64), spinup_change (Float), number of bodies (u64), then for each body:
+// model matrix (16 Float), properties, grids, flux, interior and record.
+// View factors, flux from other bodies and the host planet and illumination are not written, they are computed again
+// from the setup at the next step.
+//
+// Sequences are written as their length (u64) followed by their elements, 2-D arrays as their
+// shape (2 u64) followed by their elements in row-major order.
+pub const MAGIC: &[u8; 8] = b"KALASTCP";
+
+// Version of the format written, increased when the layout changes.
+pub const VERSION: u32 = 1;
+
+// Write the state of a simulation using a time step dt to a file.
+// The file is first written next to the path and renamed, so an interrupted write does not
+// corrupt a previous checkpoint.
+pub fn write(path: impl AsRef<Path>, dt: Float, state: &State) -> Result<()> {
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    let file = std::fs::File::create(&tmp)
+        .with_context(|| format!("Cannot create checkpoint {}", tmp.display()))?;
+    let mut w = BufWriter::new(file);
+    to_writer(&mut w, dt, state)?;
+    w.flush()?;
+    drop(w);
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Cannot write checkpoint {}", path.display()))?;
+    Ok(())
+}
+
+// Read a file written by `write`, returns the time step and the state.
+pub fn read(path: impl AsRef<Path>) -> Result<(Float, State)> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Cannot open checkpoint {}", path.display()))?;
+    from_reader(&mut BufReader::new(file))
+        .with_context(|| format!("Cannot read checkpoint {}", path.display()))
+}
+
+pub fn to_writer<W: Write>(w: &mut W, dt: Float, state: &State) -> Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&[size_of::<Float>() as u8])?;
+    write_float(w, dt)?;
+
+    write_usize(w, state.iteration)?;
+    write_usize(w, state.iteration_total)?;
+    write_usize(w, state.iteration_record)?;
+    write_float(w, state.time)?;
+    write_float(w, state.progress)?;
+    write_usize(w, state.spinup_rotations)?;
+    write_float(w, state.spinup_change)?;
+
+    write_usize(w, state.bodies.len())?;
+    for body in &state.bodies {
+        for &x in &body.state.to_cols_array() {
+            write_float(w, x)?;
+        }
+        write_usizes(w, &body.properties)?;
+
+        let mut indices: Vec<_> = body.grids.keys().copied().collect();
+        indices.sort_unstable();
+        write_usize(w, indices.len())?;
+        for index in indices {
+            let grid = &body.grids[&index];
+            write_usize(w, index)?;
+            write_array1(w, &grid.z)?;
+            write_array1(w, &grid.dtpa)?;
+            write_array1(w, &grid.dtpb)?;
+            for &x in &grid.g {
+                write_float(w, x)?;
+            }
+            write_float(w, grid.dxb)?;
+        }
+
+        write_array1(w, &body.flux)?;
+
+        let interior = &body.interior;
+        write_usizes(w, &interior.sizes)?;
+        for a in [
+            &interior.t,
+            &interior.d,
+            &interior.p,
+            &interior.c,
+            &interior.k,
+        ] {
+            write_array2(w, a)?;
+        }
+
+        let record = &body.record;
+        write_usizes(w, &record.facets)?;
+        write_array1(w, &record.time)?;
+        write_array2(w, &record.temperature_surface)?;
+        write_array2(w, &record.flux_surface)?;
+        write_array2(w, &record.flux_mutual)?;
+        write_usizes(w, &record.interior_time_indices)?;
+        write_usize(w, record.temperature_interior.len())?;
+        for columns in &record.temperature_interior {
+            write_usize(w, columns.len())?;
+            for column in columns {
+                write_array1(w, column)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn from_reader<R: Read>(r: &mut R) -> Result<(Float, State)> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(anyhow!("Not a checkpoint file"));
+    }
+    let mut version = [0u8; 4];
+    r.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != VERSION {
+        return Err(anyhow!(
+            "Checkpoint version {} is not supported, expected {}",
+            version,
+            VERSION
+        ));
+    }
+    let mut size = [0u8; 1];
+    r.read_exact(&mut size)?;
+    if size[0] as usize != size_of::<Float>() {
+        return Err(anyhow!(
+            "Checkpoint written with floats of {} bytes, expected {}",
+            size[0],
+            size_of::<Float>()
+        ));
+    }
+    let dt = read_float(r)?;
+
+    let mut state = State::new(0, 0);
+    state.iteration = read_usize(r)?;
+    state.iteration_total = read_usize(r)?;
+    state.iteration_record = read_usize(r)?;
+    state.time = read_float(r)?;
+    state.progress = read_float(r)?;
+    state.spinup_rotations = read_usize(r)?;
+    state.spinup_change = read_float(r)?;
+
+    let n = read_usize(r)?;
+    for _ in 0..n {
+        let mut matrix = [0.0; 16];
+        for x in &mut matrix {
+            *x = read_float(r)?;
+        }
+        let properties = read_usizes(r)?;
+
+        let mut grids = HashMap::new();
+        for _ in 0..read_usize(r)? {
+            let index = read_usize(r)?;
+            let z = read_array1(r)?;
+            let dtpa = read_array1(r)?;
+            let dtpb = read_array1(r)?;
+            let g = [read_float(r)?, read_float(r)?, read_float(r)?];
+            let dxb = read_float(r)?;
+            grids.insert(
+                index,
+                Grid {
+                    z,
+                    dtpa,
+                    dtpb,
+                    g,
+                    dxb,
+                },
+            );
+        }
+
+        let flux = read_array1(r)?;
+
+        let sizes = read_usizes(r)?;
+        let interior = Interior {
+            t: read_array2(r)?,
+            d: read_array2(r)?,
+            p: read_array2(r)?,
+            c: read_array2(r)?,
+            k: read_array2(r)?,
+            sizes,
+        };
+
+        let facets = read_usizes(r)?;
+        let time = read_array1(r)?;
+        let temperature_surface = read_array2(r)?;
+        let flux_surface = read_array2(r)?;
+        let flux_mutual = read_array2(r)?;
+        let mut record = BodyRecord {
+            facets,
+            time,
+            temperature_surface,
+            flux_surface,
+            flux_mutual,
+            interior_time_indices: read_usizes(r)?,
+            temperature_interior: vec![],
+        };
+        for _ in 0..read_usize(r)? {
+            let columns = (0..read_usize(r)?)
+                .map(|_| read_array1(r))
+                .collect::<Result<_>>()?;
+            record.temperature_interior.push(columns);
+        }
+
+        state.bodies.push(BodyState {
+            interior,
+            properties,
+            grids,
+            state: Mat4::from_cols_array(&matrix),
+            view_factors: None,
+            flux_mutual: Array1::zeros(flux.len()),
+            flux_planet: Array1::zeros(flux.len()),
+            illumination: Array1::ones(flux.len()),
+            flux,
+            record,
+        });
+    }
+    Ok((dt, state))
+}
+
+fn write_usize<W: Write>(w: &mut W, x: usize) -> Result<()> {
+    w.write_all(&(x as u64).to_le_bytes())?;
+    Ok(())
+}
+
+fn write_float<W: Write>(w: &mut W, x: Float) -> Result<()> {
+    w.write_all(&x.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_usizes<W: Write>(w: &mut W, v: &[usize]) -> Result<()> {
+    write_usize(w, v.len())?;
+    for &x in v {
+        write_usize(w, x)?;
+    }
+    Ok(())
+}
+
+fn write_array1<W: Write>(w: &mut W, a: &Array1<Float>) -> Result<()> {
+    write_usize(w, a.len())?;
+    for &x in a {
+        write_float(w, x)?;
+    }
+    Ok(())
+}
+
+fn write_array2<W: Write>(w: &mut W, a: &Array2<Float>) -> Result<()> {
+    write_usize(w, a.nrows())?;
+    write_usize(w, a.ncols())?;
+    for &x in a {
+        write_float(w, x)?;
+    }
+    Ok(())
+}
+
+fn read_usize<R: Read>(r: &mut R) -> Result<usize> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b) as usize)
+}
+
+fn read_float<R: Read>(r: &mut R) -> Result<Float> {
+    let mut b = [0u8; size_of::<Float>()];
+    r.read_exact(&mut b)?;
+    Ok(Float::from_le_bytes(b))
+}
+
+fn read_usizes<R: Read>(r: &mut R) -> Result<Vec<usize>> {
+    (0..read_usize(r)?).map(|_| read_usize(r)).collect()
+}
+
+// Elements read at once, a corrupted length fails at the end of the file without allocating
+// more than what was read.
+const CHUNK: usize = 1 << 16;
+
+fn read_floats<R: Read>(r: &mut R, n: usize) -> Result<Vec<Float>> {
+    let mut x = Vec::with_capacity(n.min(CHUNK));
+    let mut b = vec![0u8; n.min(CHUNK) * size_of::<Float>()];
+    while x.len() < n {
+        let b = &mut b[..(n - x.len()).min(CHUNK) * size_of::<Float>()];
+        r.read_exact(b)?;
+        x.extend(
+            b.chunks_exact(size_of::<Float>())
+                .map(|c| Float::from_le_bytes(c.try_into().unwrap())),
+        );
+    }
+    Ok(x)
+}
+
+fn read_array1<R: Read>(r: &mut R) -> Result<Array1<Float>> {
+    let n = read_usize(r)?;
+    Ok(Array1::from_vec(read_floats(r, n)?))
+}
+
+fn read_array2<R: Read>(r: &mut R) -> Result<Array2<Float>> {
+    let n = read_usize(r)?;
+    let m = read_usize(r)?;
+    let len = n
+        .checked_mul(m)
+        .ok_or_else(|| anyhow!("Array of shape ({}, {}) is too large", n, m))?;
+    Ok(Array2::from_shape_vec((n, m), read_floats(r, len)?)?)
+}
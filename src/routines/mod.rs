@@ -1 +1,3 @@
-pub mod setup;
\ No newline at end of file
+pub mod checkpoint;
+pub mod setup;
+pub mod state;
\ No newline at end of file
@@ -1,9 +1,26 @@
-use std::vec;
+use std::{collections::HashMap, vec};
 
-use ndarray::Array1;
+use anyhow::{Context, Result, anyhow};
+use glam::Vec4Swizzles;
+use ndarray::{Array1, ArrayView1, ArrayViewMut1, Axis, s};
 use pyo3::{IntoPyObjectExt, prelude::*};
 
-use crate::{Float, Mat4, Vec3};
+use super::state::{BodyRecord, BodyState, Grid, State};
+use crate::{
+    Float, Mat3, Mat4, Vec3,
+    tpm::{
+        core::SurfaceTemperatureError,
+        illumination::Occluder,
+        planetshine::PlanetTemperature,
+        routine::{
+            ColumnSolver, Layers, Nodes, Scheme, Stencil, Workspace, update_thermal_state_inplace,
+        },
+        viewfactors::{ViewFactorMethod, ViewFactors},
+    },
+};
+
+// Fewest facets updated by a thread, below which spawning threads costs more than it saves.
+const FACETS_PER_THREAD_MIN: usize = 64;
 
 #[derive(Clone)]
 pub struct ProgressDebug {
@@ -37,6 +54,9 @@ pub struct Time {
     pub dt: Float,
     pub duration_total: Float,
     pub duration_record: Float,
+
+    // sub-cycling of the columns inside a step of dt, records stay at dt
+    pub substeps: Option<Substeps>,
 }
 
 impl Time {
@@ -45,6 +65,7 @@ impl Time {
             dt: 0.0,
             duration_total: 0.0,
             duration_record: 0.0,
+            substeps: None,
         }
     }
 }
@@ -53,8 +74,58 @@ impl std::fmt::Debug for Time {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Time(dt={}, duration_total={}, duration_record={})",
-            self.dt, self.duration_total, self.duration_record,
+            "Time(dt={}, duration_total={}, duration_record={}, substeps={:?})",
+            self.dt, self.duration_total, self.duration_record, self.substeps,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Substeps {
+    // stability coef of the explicit scheme, see tpm::core::stability_maxdt
+    pub stability: Float,
+
+    // largest change of absorbed flux at surface over a substep (W/m2), refines the steps during
+    // fast transients such as eclipses or terminator crossings, shadows are cast again at each
+    // substep with illumination
+    pub flux_change: Float,
+
+    // largest number of substeps in a step of dt
+    pub max: usize,
+}
+
+impl Substeps {
+    pub fn new() -> Self {
+        Self {
+            stability: 0.5,
+            flux_change: 10.0,
+            max: 1000,
+        }
+    }
+
+    // Number of substeps to divide a step of dt.
+    //
+    // maxdt: largest stable time step of the column, none for unconditionally stable schemes (s)
+    // flux_change: change of absorbed flux at surface over the step (W/m2)
+    pub fn count(&self, dt: Float, maxdt: Option<Float>, flux_change: Float) -> usize {
+        let n_stability = maxdt.map_or(1.0, |maxdt| (dt / maxdt).ceil());
+        let n_flux = (flux_change / self.flux_change).ceil();
+        (n_stability.max(n_flux).max(1.0) as usize).min(self.max)
+    }
+}
+
+impl Default for Substeps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Substeps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Substeps(stability={}, flux_change={}, max={})",
+            self.stability, self.flux_change, self.max,
         )
     }
 }
@@ -65,6 +136,15 @@ pub enum FacetSelection {
     All,
 }
 
+impl FacetSelection {
+    pub fn indices(&self, n: usize) -> Vec<usize> {
+        match self {
+            Self::Some(facets) => facets.clone(),
+            Self::All => (0..n).collect(),
+        }
+    }
+}
+
 #[pyclass(from_py_object)]
 #[derive(Clone)]
 pub struct Record {
@@ -74,6 +154,10 @@ pub struct Record {
     #[pyo3(get, set)]
     pub flux_surface: bool,
 
+    // flux absorbed from the other bodies, see Setup::mutual_heating
+    #[pyo3(get, set)]
+    pub flux_mutual: bool,
+
     pub surface_facets: FacetSelection,
 
     #[pyo3(get, set)]
@@ -90,6 +174,7 @@ impl Record {
         Self {
             temperature_surface: false,
             flux_surface: false,
+            flux_mutual: false,
             surface_facets: FacetSelection::Some(vec![]),
             temperature_interior: false,
             interior_time_indices: vec![],
@@ -122,9 +207,10 @@ impl std::fmt::Debug for Record {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Record(temperature_surface={}, flux_surface={}, surface_facets={:?}, temperature_interior={}, interior_time_indices={:?})",
+            "Record(temperature_surface={}, flux_surface={}, flux_mutual={}, surface_facets={:?}, temperature_interior={}, interior_time_indices={:?})",
             self.temperature_surface,
             self.flux_surface,
+            self.flux_mutual,
             self.surface_facets,
             self.temperature_interior,
             self.interior_time_indices
@@ -194,13 +280,29 @@ impl DepthOption {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum GridOption {
+    Uniform,
+
+    // Depth step increasing as dx[i] = dx[i-1] * (1 + 1 / n), as in heat1d.
+    Geometric(Float),
+}
+
 #[pyclass(from_py_object)]
 #[derive(Clone)]
 pub struct SetupColumn {
     pub depth_max: DepthOption,
 
+    // depth step, or first depth step for non-uniform grids
     #[pyo3(get, set)]
     pub dx: Float,
+
+    pub grid: GridOption,
+
+    // variation of properties with depth, the thermal properties of the facet are at surface
+    pub layering: crate::tpm::column::Layering,
+
+    pub bottom: crate::tpm::routine::BottomBoundary,
 }
 
 #[pymethods]
@@ -210,9 +312,75 @@ impl SetupColumn {
         Self {
             depth_max: DepthOption::SkinDepth2pi,
             dx: 0.0,
+            grid: GridOption::Uniform,
+            layering: crate::tpm::column::Layering::Homogeneous,
+            bottom: crate::tpm::routine::BottomBoundary::Adiabatic,
         }
     }
 
+    // Depth of the bottom of the column (m).
+    pub fn set_depth_max(&mut self, x: Float) {
+        self.depth_max = DepthOption::X(x);
+    }
+
+    // Depth of the bottom of the column in number of skin depths, see SkinDepthParams.
+    pub fn set_depth_max_skin_depth_1(&mut self, n: usize) {
+        self.depth_max = DepthOption::SkinDepth1(n);
+    }
+
+    pub fn set_depth_max_skin_depth_2pi(&mut self) {
+        self.depth_max = DepthOption::SkinDepth2pi;
+    }
+
+    pub fn set_grid_uniform(&mut self) {
+        self.grid = GridOption::Uniform;
+    }
+
+    #[pyo3(signature = (n=5.0))]
+    pub fn set_grid_geometric(&mut self, n: Float) {
+        self.grid = GridOption::Geometric(n);
+    }
+
+    pub fn set_layering_homogeneous(&mut self) {
+        self.layering = crate::tpm::column::Layering::Homogeneous;
+    }
+
+    pub fn set_layering_mantle(
+        &mut self,
+        thickness: Float,
+        substrate: PyRef<'_, crate::py::tpm::properties::Properties>,
+    ) {
+        self.layering = crate::tpm::column::Layering::Mantle {
+            thickness,
+            substrate: substrate.inner.borrow().clone(),
+        };
+    }
+
+    pub fn set_layering_transition(
+        &mut self,
+        h: Float,
+        substrate: PyRef<'_, crate::py::tpm::properties::Properties>,
+    ) {
+        self.layering = crate::tpm::column::Layering::Transition {
+            h,
+            substrate: substrate.inner.borrow().clone(),
+        };
+    }
+
+    pub fn set_bottom_adiabatic(&mut self) {
+        self.bottom = crate::tpm::routine::BottomBoundary::Adiabatic;
+    }
+
+    // Internal heat flux entering the column from below (W/m2).
+    pub fn set_bottom_flux(&mut self, q: Float) {
+        self.bottom = crate::tpm::routine::BottomBoundary::Flux(q);
+    }
+
+    // Fixed temperature at the bottom of the column (K).
+    pub fn set_bottom_temperature(&mut self, t: Float) {
+        self.bottom = crate::tpm::routine::BottomBoundary::Temperature(t);
+    }
+
     pub fn __repr__(&self) -> String {
         format!("{:?}", self)
     }
@@ -222,16 +390,19 @@ impl std::fmt::Debug for SetupColumn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "SkinDepthParams(depth_max={:?}, dx={})",
-            self.depth_max, self.dx,
+            "SetupColumn(depth_max={:?}, dx={}, grid={:?}, layering={:?}, bottom={:?})",
+            self.depth_max, self.dx, self.grid, self.layering, self.bottom,
         )
     }
 }
 
 impl SetupColumn {
-    pub fn make_column(&self, params: Option<SkinDepthParams>) -> Array1<Float> {
+    pub fn make_column(&self, params: Option<SkinDepthParams>) -> Result<Array1<Float>> {
         let depth_max = self.depth_max.value(params);
-        Array1::range(0.0, depth_max + self.dx, self.dx)
+        match self.grid {
+            GridOption::Uniform => crate::tpm::column::depth_uniform(depth_max, self.dx),
+            GridOption::Geometric(n) => crate::tpm::column::depth_geometric(depth_max, self.dx, n),
+        }
     }
 }
 
@@ -241,6 +412,217 @@ pub enum Interior {
     SetupColumn(SetupColumn),
 }
 
+impl Interior {
+    pub fn make_column(&self, params: Option<SkinDepthParams>) -> Result<Array1<Float>> {
+        match self {
+            Self::Column(z) => Ok(Array1::from_vec(z.clone())),
+            Self::SetupColumn(setup) => setup.make_column(params),
+        }
+    }
+
+    pub fn layering(&self) -> Option<&crate::tpm::column::Layering> {
+        match self {
+            Self::Column(_) => None,
+            Self::SetupColumn(setup) => Some(&setup.layering),
+        }
+    }
+
+    pub fn bottom(&self) -> crate::tpm::routine::BottomBoundary {
+        match self {
+            Self::Column(_) => crate::tpm::routine::BottomBoundary::Adiabatic,
+            Self::SetupColumn(setup) => setup.bottom,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SelfHeating {
+    // remove pairs of facets hidden by another facet of the body
+    pub occlusion: bool,
+
+    // orders of scattering of sunlight, 1 for single scattering, 0 for none
+    pub scattering: usize,
+
+    // thermal emission of the other facets
+    pub thermal: bool,
+
+    // pairs of facets with view factors not above are removed
+    pub cutoff: Float,
+
+    // estimator of the view factors
+    pub method: ViewFactorMethod,
+
+    // directory where view factors are saved and read again for the same mesh, computed at each
+    // preparation otherwise
+    pub cache: Option<std::path::PathBuf>,
+}
+
+impl SelfHeating {
+    pub fn new() -> Self {
+        Self {
+            occlusion: true,
+            scattering: 1,
+            thermal: true,
+            cutoff: 0.0,
+            method: ViewFactorMethod::Analytic,
+            cache: None,
+        }
+    }
+}
+
+impl Default for SelfHeating {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for SelfHeating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SelfHeating(occlusion={}, scattering={}, thermal={}, cutoff={}, method={:?}, cache={:?})",
+            self.occlusion, self.scattering, self.thermal, self.cutoff, self.method, self.cache,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct MutualHeating {
+    // sunlight scattered once by the other bodies
+    pub scattering: bool,
+
+    // thermal emission of the other bodies
+    pub thermal: bool,
+
+    // pairs of facets hidden by another facet of either body are removed
+    pub occlusion: bool,
+
+    // pairs of facets with view factors not above are removed
+    pub cutoff: Float,
+}
+
+impl MutualHeating {
+    pub fn new() -> Self {
+        Self {
+            scattering: true,
+            thermal: true,
+            occlusion: true,
+            cutoff: 0.0,
+        }
+    }
+}
+
+impl Default for MutualHeating {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for MutualHeating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MutualHeating(scattering={}, thermal={}, occlusion={}, cutoff={})",
+            self.scattering, self.thermal, self.occlusion, self.cutoff,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Illumination {
+    // shadows cast by the other bodies, only self-shadowing otherwise
+    pub cast: bool,
+
+    // subdivisions of the edges of the facets for partial shadows, see
+    // tpm::illumination::sample_points
+    pub subdivisions: usize,
+
+    // points of the disk of the Sun for penumbras, 1 for a point Sun, see
+    // tpm::illumination::visible_sun_fraction
+    pub sun_samples: usize,
+
+    // bodies casting shadows that are not simulated, ellipsoids of the radii of the entities at
+    // fixed model matrices (e.g. Mars for Phobos)
+    pub ellipsoids: Vec<(crate::entity::Body, Mat4)>,
+}
+
+impl Illumination {
+    pub fn new() -> Self {
+        Self {
+            cast: true,
+            subdivisions: 1,
+            sun_samples: 1,
+            ellipsoids: vec![],
+        }
+    }
+}
+
+impl Default for Illumination {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Illumination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Illumination(cast={}, subdivisions={}, sun_samples={}, ellipsoids={:?})",
+            self.cast, self.subdivisions, self.sun_samples, self.ellipsoids,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct HostPlanet {
+    // ellipsoid of the radii of the entity
+    pub body: crate::entity::Body,
+
+    // model matrix, fixed
+    pub state: Mat4,
+
+    pub albedo: Float,
+    pub emissivity: Float,
+    pub temperature: PlanetTemperature,
+
+    // bands of latitude of the surface elements, see tpm::planetshine::ellipsoid_elements
+    pub resolution: usize,
+
+    // shadows cast by the planet on the bodies, needs illumination
+    pub cast: bool,
+}
+
+impl HostPlanet {
+    // Planet at the origin of the world frame, albedo and emissivity of Mars.
+    pub fn new(body: crate::entity::Body) -> Self {
+        Self {
+            body,
+            state: Mat4::IDENTITY,
+            albedo: 0.25,
+            emissivity: 0.95,
+            temperature: PlanetTemperature::Equilibrium { night: 0.0 },
+            resolution: 32,
+            cast: true,
+        }
+    }
+}
+
+impl std::fmt::Debug for HostPlanet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "HostPlanet(body={:?}, state={}, albedo={}, emissivity={}, temperature={:?}, resolution={}, cast={})",
+            self.body,
+            &self.state,
+            self.albedo,
+            self.emissivity,
+            self.temperature,
+            self.resolution,
+            self.cast,
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct Body {
     pub mesh: crate::mesh::Mesh,
@@ -250,6 +632,9 @@ pub struct Body {
     pub spin_axis: Vec3,
     pub orbit_period: Float,
     pub orbit_axis: Vec3,
+
+    // scattered sunlight and thermal emission between facets of the body
+    pub self_heating: Option<SelfHeating>,
 }
 
 impl Body {
@@ -262,7 +647,29 @@ impl Body {
             spin_axis: Vec3::Z,
             orbit_period: 0.0,
             orbit_axis: Vec3::Z,
+            self_heating: None,
+        }
+    }
+
+    // Model matrix after some time.
+    // The body spins around its spin axis (body-fixed frame) and its position orbits around the
+    // orbit axis (world frame). Null periods are considered as no motion.
+    pub fn state_at(&self, time: Float) -> Mat4 {
+        let mut state = self.state;
+
+        if self.spin_period != 0.0 {
+            let angle = 2.0 * crate::util::PI * time / self.spin_period;
+            state *= Mat4::from_axis_angle(self.spin_axis.normalize(), angle);
+        }
+
+        if self.orbit_period != 0.0 {
+            let angle = 2.0 * crate::util::PI * time / self.orbit_period;
+            let position =
+                Mat3::from_axis_angle(self.orbit_axis.normalize(), angle) * state.w_axis.xyz();
+            state.w_axis = position.extend(1.0);
         }
+
+        state
     }
 }
 
@@ -270,7 +677,7 @@ impl std::fmt::Debug for Body {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Body(mesh={:?}, interior={:?}, state={}, spin_period={}, spin_axis={}, orbit_period={}, orbit_axis={})",
+            "Body(mesh={:?}, interior={:?}, state={}, spin_period={}, spin_axis={}, orbit_period={}, orbit_axis={}, self_heating={:?})",
             self.mesh,
             self.interior,
             &self.state,
@@ -278,14 +685,30 @@ impl std::fmt::Debug for Body {
             &self.spin_axis,
             self.orbit_period,
             &self.orbit_axis,
+            self.self_heating,
         )
     }
 }
 
+#[derive(Clone, Debug)]
+pub enum InitialTemperature {
+    // Effective temperature of the body at all depths.
+    Effective,
+
+    // Profile from the absorbed flux of each facet averaged over n samples of a rotation, see
+    // tpm::column::temperature_profile. The surface is at radiative equilibrium with the absorbed
+    // flux at start, not below the deep temperature. Facets never illuminated start at the
+    // effective temperature.
+    Diurnal(usize),
+}
+
 #[derive(Clone)]
 pub struct BodyDataMap {
+    // initial columns, one for all facets or one per facet, otherwise see initial
     pub temperatures: Vec<Array1<Float>>,
 
+    pub initial: InitialTemperature,
+
     // thermal properties for the whole body
     pub thermal_properties_all: usize,
 
@@ -299,6 +722,7 @@ impl BodyDataMap {
     pub fn new() -> Self {
         Self {
             temperatures: vec![],
+            initial: InitialTemperature::Effective,
             thermal_properties_all: 0,
             thermal_properties_map: vec![],
             record: Record::new(),
@@ -310,8 +734,9 @@ impl std::fmt::Debug for BodyDataMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "BodyDataMap(temperatures={:?}, thermal_properties_all={}, thermal_properties_map={:?}, record={:?})",
+            "BodyDataMap(temperatures={:?}, initial={:?}, thermal_properties_all={}, thermal_properties_map={:?}, record={:?})",
             &self.temperatures,
+            self.initial,
             self.thermal_properties_all,
             self.thermal_properties_map,
             self.record,
@@ -319,6 +744,68 @@ impl std::fmt::Debug for BodyDataMap {
     }
 }
 
+#[derive(Clone)]
+pub struct Spinup {
+    // largest change of temperatures between the ends of consecutive rotations (K)
+    pub tolerance: Float,
+
+    pub max_rotations: usize,
+}
+
+impl Spinup {
+    pub fn new() -> Self {
+        Self {
+            tolerance: 0.1,
+            max_rotations: 100,
+        }
+    }
+}
+
+impl Default for Spinup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Spinup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Spinup(tolerance={}, max_rotations={})",
+            self.tolerance, self.max_rotations,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    // steps between two checkpoints
+    pub every: usize,
+
+    // file overwritten at each checkpoint
+    pub path: std::path::PathBuf,
+}
+
+impl Checkpoint {
+    pub fn new(every: usize, path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            every,
+            path: path.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Checkpoint(every={}, path={})",
+            self.every,
+            self.path.display(),
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct Setup {
     pub sun_position: Vec3,
@@ -327,6 +814,34 @@ pub struct Setup {
     pub bodies_data_map: Vec<BodyDataMap>,
     pub progress_debug: ProgressDebug,
     pub time: Time,
+    pub scheme: crate::tpm::routine::Scheme,
+
+    // tolerance of the surface temperature and fallback when it does not converge
+    pub solver: crate::tpm::routine::SurfaceSolver,
+
+    // rotations repeated before the start of the simulation until convergence
+    pub spinup: Option<Spinup>,
+
+    // threads updating the facets, all available if 0
+    pub threads: usize,
+
+    // shadows from rays cast towards the Sun, computed again at each step and substep, all
+    // facets facing the Sun are lit otherwise
+    pub illumination: Option<Illumination>,
+
+    // scattered sunlight and thermal emission between the bodies, view factors between bodies
+    // are computed again at each step as the bodies move
+    pub mutual_heating: Option<MutualHeating>,
+
+    // large planet the bodies orbit, its reflected sunlight and thermal emission are added to
+    // the flux of the facets
+    pub planet: Option<HostPlanet>,
+
+    // state written to a file periodically during the simulation
+    pub checkpoint: Option<Checkpoint>,
+
+    // simulation state, created with `prepare`
+    pub state: Option<State>,
 }
 
 impl Setup {
@@ -338,23 +853,1039 @@ impl Setup {
             bodies_data_map: vec![],
             progress_debug: ProgressDebug::new(),
             time: Time::new(),
+            scheme: crate::tpm::routine::Scheme::Explicit,
+            solver: crate::tpm::routine::SurfaceSolver::default(),
+            spinup: None,
+            threads: 0,
+            illumination: None,
+            mutual_heating: None,
+            planet: None,
+            checkpoint: None,
+            state: None,
+        }
+    }
+
+    // Create columns, depth grids and record buffers of all bodies, then spin up if configured.
+    // Temperatures from the data map are used as initial columns (one for all facets or one per
+    // facet), otherwise columns are filled with the effective temperature of the properties.
+    pub fn prepare(&mut self) -> Result<()> {
+        self.initialize()?;
+        self.reset_flux();
+        if let Some(spinup) = self.spinup.clone() {
+            self.spin_up(&spinup)?;
+        }
+        self.record();
+        Ok(())
+    }
+
+    // Prepare with the temperatures of the columns of a checkpoint as initial columns, instead
+    // of the data maps and the spin-up. Time and records start over, so a single spin-up can
+    // be reused for simulations with other durations or records.
+    pub fn prepare_from_checkpoint(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let (_, checkpoint) = super::checkpoint::read(path)?;
+        self.check_checkpoint(&checkpoint)?;
+        self.initialize()?;
+        let state = self.state.as_mut().unwrap();
+        for (body_state, restored) in state.bodies.iter_mut().zip(checkpoint.bodies) {
+            if body_state.interior.sizes != restored.interior.sizes {
+                return Err(anyhow!(
+                    "Depth grids of the checkpoint do not match the setup"
+                ));
+            }
+            body_state.interior.t = restored.interior.t;
+        }
+        state.spinup_rotations = checkpoint.spinup_rotations;
+        state.spinup_change = checkpoint.spinup_change;
+        // flux from the restored surface temperatures
+        self.reset_flux();
+        self.record();
+        Ok(())
+    }
+
+    // Write the current state to continue the simulation later with `load_checkpoint`.
+    pub fn save_checkpoint(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let state = self
+            .state
+            .as_ref()
+            .ok_or(anyhow!("Nothing to checkpoint before prepare"))?;
+        super::checkpoint::write(path, self.time.dt, state)
+    }
+
+    // Replace the state with a checkpoint written with the same setup, stepping then continues
+    // exactly as the simulation that wrote it.
+    pub fn load_checkpoint(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let (dt, state) = super::checkpoint::read(path)?;
+        if dt != self.time.dt {
+            return Err(anyhow!(
+                "Checkpoint written with time step {}, setup uses {}",
+                dt,
+                self.time.dt
+            ));
+        }
+        self.check_checkpoint(&state)?;
+        let mut state = state;
+        for (body, body_state) in self.bodies.iter().zip(&mut state.bodies) {
+            body_state.view_factors = view_factors(body, self.threads)?;
+        }
+        self.state = Some(state);
+        Ok(())
+    }
+
+    fn check_checkpoint(&self, state: &State) -> Result<()> {
+        if state.bodies.len() != self.bodies.len() {
+            return Err(anyhow!(
+                "Checkpoint has {} bodies, setup has {}",
+                state.bodies.len(),
+                self.bodies.len()
+            ));
+        }
+        for (body, body_state) in self.bodies.iter().zip(&state.bodies) {
+            let nf = body.mesh.facets.len();
+            if body_state.interior.len() != nf || body_state.properties.len() != nf {
+                return Err(anyhow!(
+                    "Checkpoint has {} facets, setup has {}",
+                    body_state.interior.len(),
+                    nf
+                ));
+            }
+            if body_state
+                .properties
+                .iter()
+                .any(|&index| index >= self.thermal_properties.len())
+            {
+                return Err(anyhow!("Checkpoint thermal properties index out of bounds"));
+            }
+            if body_state.record.facets.iter().any(|&ii| ii >= nf) {
+                return Err(anyhow!("Checkpoint recorded facet out of bounds"));
+            }
+        }
+        Ok(())
+    }
+
+    // Create the state at the start of the simulation, the flux is left to `reset_flux`.
+    fn initialize(&mut self) -> Result<()> {
+        if self.time.dt <= 0.0 {
+            return Err(anyhow!("Time step must be positive"));
+        }
+        if self.bodies.len() != self.bodies_data_map.len() {
+            return Err(anyhow!(
+                "Expected one data map per body, got {} for {} bodies",
+                self.bodies_data_map.len(),
+                self.bodies.len()
+            ));
+        }
+
+        let dt = self.time.dt;
+        let iteration_total = (self.time.duration_total / dt).ceil() as usize;
+        let iteration_record = (iteration_total + 1)
+            .saturating_sub((self.time.duration_record / dt).ceil() as usize + 1);
+        let n_record = iteration_total + 1 - iteration_record;
+
+        let mut state = State::new(iteration_total, iteration_record);
+
+        for (body, data) in self.bodies.iter().zip(&self.bodies_data_map) {
+            let nf = body.mesh.facets.len();
+            let body_state = body.state_at(0.0);
+
+            let mut properties = vec![data.thermal_properties_all; nf];
+            for &(facet, index) in &data.thermal_properties_map {
+                properties[facet] = index;
+            }
+
+            let mut grids = HashMap::new();
+            for &index in &properties {
+                let prop = self
+                    .thermal_properties
+                    .get(index)
+                    .ok_or(anyhow!("Thermal properties index {} out of bounds", index))?;
+                if let std::collections::hash_map::Entry::Vacant(entry) = grids.entry(index) {
+                    let params = SkinDepthParams {
+                        diffusivity: prop.diffusivity,
+                        period: body.spin_period,
+                    };
+                    let z = body
+                        .interior
+                        .make_column(Some(params))
+                        .with_context(|| format!("Depth grid of thermal properties {}", index))?;
+                    entry.insert(Grid::new(z, dt));
+                }
+            }
+
+            let sun = body_state.inverse().transform_point3(self.sun_position);
+            let dau = sun.length() / crate::util::AU;
+
+            // position of the Sun in the body-fixed frame over a rotation
+            let suns = match data.initial {
+                InitialTemperature::Effective => vec![],
+                InitialTemperature::Diurnal(n) => (0..n)
+                    .map(|jj| {
+                        body.state_at(jj as Float / n as Float * body.spin_period)
+                            .inverse()
+                            .transform_point3(self.sun_position)
+                    })
+                    .collect(),
+            };
+
+            let mut columns = Vec::with_capacity(nf);
+            let mut flux = Array1::zeros(nf);
+            for ii in 0..nf {
+                let prop = &self.thermal_properties[properties[ii]];
+                let grid = &grids[&properties[ii]];
+                let t_init = crate::tpm::core::effective_temperature(
+                    dau,
+                    0.25,
+                    prop.albedo,
+                    prop.emissivity,
+                );
+                let mut column = match body.interior.layering() {
+                    Some(layering) => crate::tpm::column::Column::new_layered(
+                        grid.z.clone(),
+                        prop,
+                        layering,
+                        t_init,
+                    ),
+                    None => crate::tpm::column::Column::new(grid.z.clone(), prop.clone(), t_init),
+                };
+
+                // without shadows, only for the initial profile
+                flux[ii] = flux_sun(&body.mesh.facets[ii], sun, prop, 1.0);
+
+                let flux_mean = suns
+                    .iter()
+                    .map(|&sun| flux_sun(&body.mesh.facets[ii], sun, prop, 1.0))
+                    .sum::<Float>()
+                    / suns.len().max(1) as Float;
+                if flux_mean > 0.0 {
+                    let se = crate::util::STEFAN_BOLTZMANN * prop.emissivity;
+                    let t_deep = (flux_mean / se).powf(0.25);
+                    let t_surface = (flux[ii].max(flux_mean) / se).powf(0.25);
+                    let skin_depth =
+                        crate::tpm::properties::skin_depth_1(prop.diffusivity, body.spin_period);
+                    column.t = crate::tpm::column::temperature_profile(
+                        column.z.view(),
+                        t_surface,
+                        t_deep,
+                        skin_depth,
+                    );
+                }
+
+                let t = match data.temperatures.len() {
+                    0 => None,
+                    1 => Some(&data.temperatures[0]),
+                    n if n == nf => Some(&data.temperatures[ii]),
+                    n => {
+                        return Err(anyhow!(
+                            "Expected 1 or {} initial temperature columns, got {}",
+                            nf,
+                            n
+                        ));
+                    }
+                };
+                if let Some(t) = t {
+                    if t.len() != column.t.len() {
+                        return Err(anyhow!(
+                            "Initial temperature column of size {} does not match depth grid of size {}",
+                            t.len(),
+                            column.t.len()
+                        ));
+                    }
+                    column.t.assign(t);
+                }
+
+                columns.push(column);
+            }
+
+            let record = &data.record;
+            let facets = record.surface_facets.indices(nf);
+            if let Some(&ii) = facets.iter().find(|&&ii| ii >= nf) {
+                return Err(anyhow!(
+                    "Recorded facet {} out of bounds, body has {} facets",
+                    ii,
+                    nf
+                ));
+            }
+            let record = BodyRecord::new(
+                facets,
+                n_record,
+                record.temperature_surface,
+                record.flux_surface,
+                record.flux_mutual,
+            );
+
+            state.bodies.push(BodyState {
+                interior: crate::tpm::column::Interior::from_columns(&columns),
+                properties,
+                grids,
+                state: body_state,
+                flux,
+                view_factors: view_factors(body, self.threads)?,
+                flux_mutual: Array1::zeros(nf),
+                flux_planet: Array1::zeros(nf),
+                illumination: Array1::ones(nf),
+                record,
+            });
+        }
+
+        self.state = Some(state);
+        Ok(())
+    }
+
+    // Repeat the first rotation until the temperatures of all columns at the end of consecutive
+    // rotations differ less than the tolerance, or the maximum number of rotations is reached.
+    // The rotation is the longest spin period of the bodies, rounded to a multiple of dt.
+    fn spin_up(&mut self, spinup: &Spinup) -> Result<()> {
+        let dt = self.time.dt;
+        let period = self
+            .bodies
+            .iter()
+            .fold(0.0, |period: Float, body| period.max(body.spin_period));
+        if period <= 0.0 {
+            return Err(anyhow!("Spin-up needs a body with a spin period"));
+        }
+        let n = ((period / dt).round() as usize).max(1);
+
+        for rotation in 1..=spinup.max_rotations {
+            let before: Vec<_> = self
+                .state
+                .as_ref()
+                .unwrap()
+                .bodies
+                .iter()
+                .map(|b| b.interior.t.clone())
+                .collect();
+
+            for jj in 0..n {
+                self.advance(jj as Float * dt, dt)?;
+            }
+
+            let state = self.state.as_mut().unwrap();
+            let mut change: Float = 0.0;
+            for (body_state, before) in state.bodies.iter().zip(&before) {
+                change = (&body_state.interior.t - before).fold(change, |a, &b| a.max(b.abs()));
+            }
+            state.spinup_rotations = rotation;
+            state.spinup_change = change;
+            if change < spinup.tolerance {
+                break;
+            }
+        }
+
+        // back to the geometry at start
+        let state = self.state.as_mut().unwrap();
+        for (body, body_state) in self.bodies.iter().zip(&mut state.bodies) {
+            body_state.state = body.state_at(0.0);
+        }
+        self.reset_flux();
+        Ok(())
+    }
+
+    // Advance the simulation of one time step.
+    // Returns false if the simulation was already finished.
+    pub fn step(&mut self) -> Result<bool> {
+        if self.state.is_none() {
+            self.prepare()?;
+        }
+
+        let dt = self.time.dt;
+        let state = self.state.as_ref().unwrap();
+
+        if state.is_finished() {
+            return Ok(false);
+        }
+
+        let time = state.time;
+        self.advance(time, dt)?;
+
+        let state = self.state.as_mut().unwrap();
+        state.iteration += 1;
+        state.time = time + dt;
+
+        self.record();
+        self.show_progress();
+
+        if let Some(checkpoint) = &self.checkpoint {
+            let iteration = self.state.as_ref().unwrap().iteration;
+            if checkpoint.every > 0 && iteration.is_multiple_of(checkpoint.every) {
+                self.save_checkpoint(&checkpoint.path)?;
+            }
+        }
+        Ok(true)
+    }
+
+    // Flux absorbed by the facets of all bodies at their current model matrices and surface
+    // temperatures, as used at the start of a step.
+    fn reset_flux(&mut self) {
+        self.update_between_bodies();
+        let state = self.state.as_mut().unwrap();
+        for (body, body_state) in self.bodies.iter().zip(&mut state.bodies) {
+            let sun = body_state
+                .state
+                .inverse()
+                .transform_point3(self.sun_position);
+            for (ii, facet) in body.mesh.facets.iter().enumerate() {
+                let prop = &self.thermal_properties[body_state.properties[ii]];
+                body_state.flux[ii] = flux_sun(facet, sun, prop, body_state.illumination[ii]);
+            }
+            if let Some(heating) =
+                flux_self_heating(body, body_state, sun, &self.thermal_properties)
+            {
+                body_state.flux += &heating;
+            }
+            if self.mutual_heating.is_some() {
+                body_state.flux += &body_state.flux_mutual;
+            }
+            if self.planet.is_some() {
+                body_state.flux += &body_state.flux_planet;
+            }
+        }
+    }
+
+    // Shadows and heating between bodies at their current model matrices and surface
+    // temperatures, before any body is updated.
+    fn update_between_bodies(&mut self) {
+        self.update_illumination();
+        self.update_mutual_heating();
+        self.update_planetshine();
+    }
+
+    // Compute the illuminated fraction of the facets of all bodies at their current model
+    // matrices, see tpm::illumination::illumination.
+    fn update_illumination(&mut self) {
+        let Some(params) = &self.illumination else {
+            return;
+        };
+        let state = self.state.as_mut().unwrap();
+        let matrices: Vec<_> = state.bodies.iter().map(|b| b.state).collect();
+        for (a, (body, body_state)) in self.bodies.iter().zip(&mut state.bodies).enumerate() {
+            let occluders = occluders(&self.bodies, a, &matrices, params, self.planet.as_ref());
+            body_state.illumination = crate::tpm::illumination::illumination(
+                &body.mesh,
+                matrices[a].inverse().transform_point3(self.sun_position),
+                &occluders,
+                params.subdivisions,
+                params.sun_samples,
+            );
+        }
+    }
+
+    // Compute the flux absorbed by each body from the other bodies at their current model
+    // matrices and surface temperatures, see flux_mutual_heating.
+    fn update_mutual_heating(&mut self) {
+        let Some(params) = &self.mutual_heating else {
+            return;
+        };
+        let state = self.state.as_mut().unwrap();
+        let fluxes = flux_mutual_heating(
+            params,
+            &self.bodies,
+            &state.bodies,
+            self.sun_position,
+            &self.thermal_properties,
+            self.threads,
+        );
+        for (body_state, flux) in state.bodies.iter_mut().zip(fluxes) {
+            body_state.flux_mutual = flux;
+        }
+    }
+
+    // Compute the flux absorbed by each body from the host planet at their current model
+    // matrices, see flux_planetshine.
+    fn update_planetshine(&mut self) {
+        let Some(planet) = &self.planet else {
+            return;
+        };
+        let elements =
+            crate::tpm::planetshine::ellipsoid_elements(planet.body.radii, planet.resolution);
+        let state = self.state.as_mut().unwrap();
+        for (body, body_state) in self.bodies.iter().zip(&mut state.bodies) {
+            let flux = flux_planetshine(
+                planet,
+                &elements,
+                body,
+                body_state,
+                self.sun_position,
+                &self.thermal_properties,
+            );
+            body_state.flux_planet = flux;
+        }
+    }
+
+    // Update the columns of all bodies from a time to the next after dt, see try_advance.
+    // A failed step leaves the state untouched: the geometry and the fluxes between bodies are
+    // restored, and the columns are only committed once all facets succeeded.
+    fn advance(&mut self, time_prev: Float, dt: Float) -> Result<()> {
+        let previous: Vec<_> = self
+            .state
+            .as_ref()
+            .unwrap()
+            .bodies
+            .iter()
+            .map(|b| {
+                (
+                    b.state,
+                    b.illumination.clone(),
+                    b.flux_mutual.clone(),
+                    b.flux_planet.clone(),
+                )
+            })
+            .collect();
+        let result = self.try_advance(time_prev, dt);
+        if result.is_err() {
+            let state = self.state.as_mut().unwrap();
+            for (body_state, (matrix, illumination, mutual, planet)) in
+                state.bodies.iter_mut().zip(previous)
+            {
+                body_state.state = matrix;
+                body_state.illumination = illumination;
+                body_state.flux_mutual = mutual;
+                body_state.flux_planet = planet;
+            }
+        }
+        result
+    }
+
+    // Facets are split in contiguous chunks updated in parallel into copies of the columns.
+    fn try_advance(&mut self, time_prev: Float, dt: Float) -> Result<()> {
+        let time = time_prev + dt;
+        for (body, body_state) in self
+            .bodies
+            .iter()
+            .zip(&mut self.state.as_mut().unwrap().bodies)
+        {
+            body_state.state = body.state_at(time);
+        }
+
+        // shadows at the new geometry and heating between bodies from the surface temperatures
+        // of the previous step, before any body is updated
+        self.update_between_bodies();
+
+        let state = self.state.as_mut().unwrap();
+        let threads = match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let thermal_properties = &self.thermal_properties;
+        let sun_position = self.sun_position;
+        let substeps = self.time.substeps.as_ref();
+        let (scheme, surface) = (self.scheme, self.solver);
+        let bodies = &self.bodies;
+        let (shadows, planet_params) = (self.illumination.as_ref(), self.planet.as_ref());
+
+        // updated columns and fluxes of each body, committed once all bodies succeeded
+        let mut updated = vec![];
+        for (a, (body, body_state)) in bodies.iter().zip(&mut state.bodies).enumerate() {
+            let sun = body_state.state.inverse().transform_point3(sun_position);
+            let layered = body
+                .interior
+                .layering()
+                .is_some_and(|l| !l.is_homogeneous());
+            let solver = ColumnSolver {
+                scheme,
+                bottom: body.interior.bottom(),
+                surface,
+            };
+
+            // self-heating from the surface temperatures of the previous step, constant over
+            // substeps
+            let heating = flux_self_heating(body, body_state, sun, thermal_properties);
+            let heating = heating.as_ref();
+            let mutual = self
+                .mutual_heating
+                .is_some()
+                .then_some(&body_state.flux_mutual);
+            let planet = self.planet.is_some().then_some(&body_state.flux_planet);
+
+            let BodyState {
+                interior,
+                properties,
+                grids,
+                flux,
+                illumination,
+                ..
+            } = body_state;
+            let crate::tpm::column::Interior {
+                t,
+                d,
+                p,
+                c,
+                k,
+                sizes,
+            } = interior;
+            let (d, p, c, k) = (&*d, &*p, &*c, &*k);
+            let (properties, grids, sizes, illumination) =
+                (&*properties, &*grids, &*sizes, &*illumination);
+            if sizes.is_empty() {
+                updated.push(None);
+                continue;
+            }
+            let (mut t, mut flux) = (t.clone(), flux.clone());
+            let chunk = sizes.len().div_ceil(threads).max(FACETS_PER_THREAD_MIN);
+
+            let update = |ii: usize,
+                          mut t: ArrayViewMut1<'_, Float>,
+                          flux_prev: &mut Float,
+                          work: &mut Workspace,
+                          dtpa_sub: &mut Vec<Float>,
+                          dtpb_sub: &mut Vec<Float>|
+             -> Result<(), SurfaceTemperatureError> {
+                let facet = &body.mesh.facets[ii];
+                let prop = &thermal_properties[properties[ii]];
+                let grid = &grids[&properties[ii]];
+                let m = sizes[ii];
+                let nodes = if layered || prop.is_temperature_dependent() {
+                    Nodes::Layered(Layers {
+                        p: p.slice(s![ii, ..m]),
+                        c: c.slice(s![ii, ..m]),
+                        k: k.slice(s![ii, ..m]),
+                    })
+                } else {
+                    Nodes::Diffusivity(d.slice(s![ii, ..m]))
+                };
+                let heating = heating.map_or(0.0, |h| h[ii])
+                    + mutual.map_or(0.0, |h| h[ii])
+                    + planet.map_or(0.0, |h| h[ii]);
+                let illuminated = illumination[ii];
+                let flux = flux_sun(facet, sun, prop, illuminated) + heating;
+
+                let n = substeps.map_or(1, |substeps| {
+                    let maxdt = match (scheme, nodes) {
+                        (Scheme::Implicit(_), _) => None,
+                        (Scheme::Explicit, Nodes::Diffusivity(d)) => {
+                            Some(crate::tpm::column::stability_maxdt_column(
+                                grid.z.view(),
+                                d,
+                                substeps.stability,
+                            ))
+                        }
+                        (Scheme::Explicit, Nodes::Layered(Layers { p, c, k })) => {
+                            let d = crate::tpm::column::diffusivity_nodes(t.view(), p, c, k, prop);
+                            Some(crate::tpm::column::stability_maxdt_column(
+                                grid.z.view(),
+                                d.view(),
+                                substeps.stability,
+                            ))
+                        }
+                    };
+                    substeps.count(dt, maxdt, (flux - *flux_prev).abs())
+                });
+
+                if n == 1 {
+                    update_thermal_state_inplace(
+                        t.view_mut(),
+                        flux,
+                        nodes,
+                        prop,
+                        grid.stencil(),
+                        solver,
+                        work,
+                    )
+                    .map_err(|e| facet_error(e, ii))?;
+                } else {
+                    let ratio = 1.0 / n as Float;
+                    dtpa_sub.clear();
+                    dtpa_sub.extend(grid.dtpa.iter().map(|x| x * ratio));
+                    dtpb_sub.clear();
+                    dtpb_sub.extend(grid.dtpb.iter().map(|x| x * ratio));
+                    for jj in 1..=n {
+                        let time_sub = time_prev + dt * jj as Float * ratio;
+                        let sun_sub = body
+                            .state_at(time_sub)
+                            .inverse()
+                            .transform_point3(sun_position);
+                        // shadows cast again at the geometry of the substep, for eclipses and
+                        // moving shadow edges
+                        let illuminated = shadows.map_or(illuminated, |params| {
+                            let matrices: Vec<_> =
+                                bodies.iter().map(|b| b.state_at(time_sub)).collect();
+                            crate::tpm::illumination::illuminated_fraction(
+                                &body.mesh,
+                                ii,
+                                sun_sub,
+                                &occluders(bodies, a, &matrices, params, planet_params),
+                                params.subdivisions,
+                                params.sun_samples,
+                            )
+                        });
+                        update_thermal_state_inplace(
+                            t.view_mut(),
+                            flux_sun(facet, sun_sub, prop, illuminated) + heating,
+                            nodes,
+                            prop,
+                            Stencil::Nonuniform {
+                                dtpa: ArrayView1::from(&dtpa_sub[..]),
+                                dtpb: ArrayView1::from(&dtpb_sub[..]),
+                                g: grid.g,
+                                dxb: grid.dxb,
+                            },
+                            solver,
+                            work,
+                        )
+                        .map_err(|e| facet_error(e, ii))?;
+                    }
+                }
+                *flux_prev = flux;
+                Ok(())
+            };
+            let update = &update;
+
+            let results = crossbeam::thread::scope(|scope| {
+                let handles: Vec<_> = t
+                    .axis_chunks_iter_mut(Axis(0), chunk)
+                    .zip(flux.axis_chunks_iter_mut(Axis(0), chunk))
+                    .enumerate()
+                    .map(|(ichunk, (mut t, mut flux))| {
+                        scope.spawn(move |_| -> Result<(), SurfaceTemperatureError> {
+                            let mut work = Workspace::new();
+                            let (mut dtpa_sub, mut dtpb_sub) = (vec![], vec![]);
+                            for (jj, (t, flux_prev)) in
+                                t.outer_iter_mut().zip(flux.iter_mut()).enumerate()
+                            {
+                                let ii = ichunk * chunk + jj;
+                                update(
+                                    ii,
+                                    t.slice_move(s![..sizes[ii]]),
+                                    flux_prev,
+                                    &mut work,
+                                    &mut dtpa_sub,
+                                    &mut dtpb_sub,
+                                )?;
+                            }
+                            Ok(())
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join()).collect::<Vec<_>>()
+            })
+            .map_err(|_| anyhow!("Update of facets panicked"))?;
+
+            for result in results {
+                result.map_err(|_| anyhow!("Update of facets panicked"))??;
+            }
+            updated.push(Some((t, flux)));
+        }
+
+        for (body_state, updated) in state.bodies.iter_mut().zip(updated) {
+            if let Some((t, flux)) = updated {
+                // in place, the buffer stays the same over the simulation
+                body_state.interior.t.assign(&t);
+                body_state.flux = flux;
+            }
+        }
+        Ok(())
+    }
+
+    // Run the simulation until the total duration and return the records of each body.
+    pub fn run(&mut self) -> Result<Vec<BodyRecord>> {
+        if self.state.is_none() {
+            self.prepare()?;
+        }
+
+        while self.step()? {}
+
+        Ok(self
+            .state
+            .as_mut()
+            .unwrap()
+            .bodies
+            .iter_mut()
+            .map(|b| std::mem::take(&mut b.record))
+            .collect())
+    }
+
+    fn record(&mut self) {
+        let state = self.state.as_mut().unwrap();
+
+        if !state.is_recording() {
+            return;
+        }
+
+        let it = state.iteration_recorded();
+
+        for (data, body_state) in self.bodies_data_map.iter().zip(&mut state.bodies) {
+            let record = &mut body_state.record;
+            record.time[it] = state.time;
+
+            if data.record.temperature_surface {
+                for (jj, &ii) in record.facets.iter().enumerate() {
+                    record.temperature_surface[[it, jj]] = body_state.interior.t[[ii, 0]];
+                }
+            }
+
+            if data.record.flux_surface {
+                for (jj, &ii) in record.facets.iter().enumerate() {
+                    record.flux_surface[[it, jj]] = body_state.flux[ii];
+                }
+            }
+
+            if data.record.flux_mutual {
+                for (jj, &ii) in record.facets.iter().enumerate() {
+                    record.flux_mutual[[it, jj]] = body_state.flux_mutual[ii];
+                }
+            }
+
+            if data.record.temperature_interior && data.record.interior_time_indices.contains(&it) {
+                record.interior_time_indices.push(it);
+                record.temperature_interior.push(
+                    record
+                        .facets
+                        .iter()
+                        .map(|&ii| body_state.interior.t(ii).to_owned())
+                        .collect(),
+                );
+            }
         }
     }
 
-    pub fn prepare(&mut self) {}
+    fn show_progress(&mut self) {
+        let state = self.state.as_mut().unwrap();
+        let Ok(frequency) = self.progress_debug.frequency.parse::<Float>() else {
+            return;
+        };
+        if frequency <= 0.0 {
+            return;
+        }
+
+        let progress = state.iteration as Float / state.iteration_total.max(1) as Float * 100.0;
+        if progress >= state.progress + frequency || state.iteration == state.iteration_total {
+            state.progress = (progress / frequency).floor() * frequency;
+            println!(
+                "{:w$.p$}% ({}/{}it)",
+                progress,
+                state.iteration,
+                state.iteration_total,
+                w = self.progress_debug.digits_full,
+                p = self.progress_debug.digits_decimal,
+            );
+        }
+    }
 }
 
 impl std::fmt::Debug for Setup {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Record(sun_position={}, thermal_properties={:?}, bodies={:?}, bodies_data_map={:?}, progress_debug={:?}, time={:?})",
+            "Setup(sun_position={}, thermal_properties={:?}, bodies={:?}, bodies_data_map={:?}, progress_debug={:?}, time={:?}, scheme={:?}, solver={:?}, spinup={:?}, threads={}, illumination={:?}, mutual_heating={:?}, planet={:?}, checkpoint={:?}, state={:?})",
             &self.sun_position,
             self.thermal_properties,
             self.bodies,
             self.bodies_data_map,
             self.progress_debug,
             self.time,
+            self.scheme,
+            self.solver,
+            self.spinup,
+            self.threads,
+            self.illumination,
+            self.mutual_heating,
+            self.planet,
+            self.checkpoint,
+            self.state,
+        )
+    }
+}
+
+// Bodies casting shadows on body a in its frame, see Setup::update_illumination.
+//
+// matrices: model matrices of the bodies
+fn occluders<'a>(
+    bodies: &'a [Body],
+    a: usize,
+    matrices: &[Mat4],
+    params: &Illumination,
+    planet: Option<&HostPlanet>,
+) -> Vec<Occluder<'a>> {
+    let inv = matrices[a].inverse();
+    let mut occluders = vec![Occluder::Mesh(&bodies[a].mesh, Mat4::IDENTITY)];
+    if params.cast {
+        for (b, other) in bodies.iter().enumerate().filter(|&(b, _)| b != a) {
+            occluders.push(Occluder::mesh(&other.mesh, inv * matrices[b]));
+        }
+        for (other, state) in &params.ellipsoids {
+            occluders.push(Occluder::ellipsoid(other.radii, inv * *state));
+        }
+    }
+    if let Some(planet) = planet.filter(|p| p.cast) {
+        occluders.push(Occluder::ellipsoid(planet.body.radii, inv * planet.state));
+    }
+    occluders
+}
+
+// Absorbed flux from the Sun at the surface of a facet.
+//
+// sun: position of the Sun in the body-fixed frame (m)
+// illuminated: fraction of the facet not in shadow
+fn flux_sun(
+    facet: &crate::mesh::Facet,
+    sun: Vec3,
+    prop: &crate::tpm::properties::Properties,
+    illuminated: Float,
+) -> Float {
+    let v_sun = sun - facet.pos;
+    let d_sun = v_sun.length();
+    let cosi = crate::math::cosine_incidence(&(v_sun / d_sun), &facet.normal);
+    crate::tpm::core::radiation_sun(d_sun / crate::util::AU, cosi * illuminated, prop.albedo)
+}
+
+// Sunlight received at the surface of a facet, before absorption.
+fn irradiance_sun(facet: &crate::mesh::Facet, sun: Vec3, illuminated: Float) -> Float {
+    let v_sun = sun - facet.pos;
+    let d_sun = v_sun.length();
+    let cosi = crate::math::cosine_incidence(&(v_sun / d_sun), &facet.normal);
+    crate::tpm::core::radiation_sun(d_sun / crate::util::AU, cosi * illuminated, 0.0)
+}
+
+// View factors of a body with self-heating, read from the cache if configured.
+fn view_factors(body: &Body, threads: usize) -> Result<Option<ViewFactors>> {
+    let Some(params) = &body.self_heating else {
+        return Ok(None);
+    };
+    let (mesh, method) = (&body.mesh, params.method);
+    let (occlusion, cutoff) = (params.occlusion, params.cutoff);
+    let vf = match &params.cache {
+        Some(dir) => crate::tpm::heating::view_factor_matrix_cached(
+            mesh, method, occlusion, cutoff, threads, dir,
+        )?,
+        None => crate::tpm::heating::view_factor_matrix(mesh, method, occlusion, cutoff, threads),
+    };
+    Ok(Some(vf))
+}
+
+// Flux absorbed by each facet of a body from the other facets, see tpm::heating::self_heating.
+fn flux_self_heating(
+    body: &Body,
+    body_state: &BodyState,
+    sun: Vec3,
+    thermal_properties: &[crate::tpm::properties::Properties],
+) -> Option<Array1<Float>> {
+    let params = body.self_heating.as_ref()?;
+    let vf = body_state.view_factors.as_ref()?;
+    let (irradiance, albedo, emissivity) =
+        surface_optics(body, body_state, sun, thermal_properties, params.thermal);
+    let t = body_state.interior.t.column(0);
+
+    let heating = crate::tpm::heating::self_heating(
+        vf,
+        irradiance.view(),
+        t,
+        albedo.view(),
+        emissivity.view(),
+        params.scattering,
+    );
+    Some(heating)
+}
+
+// Sunlight received, albedo and emissivity of each facet of a body.
+// Without thermal emission, facets neither emit nor absorb in infrared (null emissivity).
+fn surface_optics(
+    body: &Body,
+    body_state: &BodyState,
+    sun: Vec3,
+    thermal_properties: &[crate::tpm::properties::Properties],
+    thermal: bool,
+) -> (Array1<Float>, Array1<Float>, Array1<Float>) {
+    let prop = |ii: usize| &thermal_properties[body_state.properties[ii]];
+    let nf = body.mesh.facets.len();
+    let irradiance = Array1::from_shape_fn(nf, |ii| {
+        irradiance_sun(&body.mesh.facets[ii], sun, body_state.illumination[ii])
+    });
+    let albedo = Array1::from_shape_fn(nf, |ii| prop(ii).albedo);
+    let emissivity = Array1::from_shape_fn(nf, |ii| match thermal {
+        true => prop(ii).emissivity,
+        false => 0.0,
+    });
+    (irradiance, albedo, emissivity)
+}
+
+// Flux absorbed by each facet of a body from the host planet, see tpm::planetshine::planetshine.
+fn flux_planetshine(
+    planet: &HostPlanet,
+    elements: &[crate::mesh::Facet],
+    body: &Body,
+    body_state: &BodyState,
+    sun_position: Vec3,
+    thermal_properties: &[crate::tpm::properties::Properties],
+) -> Array1<Float> {
+    let inv = body_state.state.inverse();
+    let (reflected, thermal) = crate::tpm::planetshine::planetshine(
+        &body.mesh.facets,
+        elements,
+        &(inv * planet.state),
+        inv.transform_point3(sun_position),
+        planet.albedo,
+        planet.emissivity,
+        planet.temperature,
+    );
+    let prop = |ii: usize| &thermal_properties[body_state.properties[ii]];
+    Array1::from_shape_fn(body.mesh.facets.len(), |ii| {
+        (1.0 - prop(ii).albedo) * reflected[ii] + prop(ii).emissivity * thermal[ii]
+    })
+}
+
+// Flux absorbed by each facet of each body from the other bodies, see
+// tpm::heating::mutual_heating. View factors are computed from the current model matrices, once
+// for both bodies of each pair.
+fn flux_mutual_heating(
+    params: &MutualHeating,
+    bodies: &[Body],
+    body_states: &[BodyState],
+    sun_position: Vec3,
+    thermal_properties: &[crate::tpm::properties::Properties],
+    threads: usize,
+) -> Vec<Array1<Float>> {
+    let optics: Vec<_> = bodies
+        .iter()
+        .zip(body_states)
+        .map(|(body, body_state)| {
+            let sun = body_state.state.inverse().transform_point3(sun_position);
+            let (mut irradiance, albedo, emissivity) =
+                surface_optics(body, body_state, sun, thermal_properties, params.thermal);
+            if !params.scattering {
+                irradiance.fill(0.0);
+            }
+            (irradiance, albedo, emissivity)
+        })
+        .collect();
+
+    // flux absorbed by a body from another one, from the view factors of the receiving body
+    let absorbed = |vf: &ViewFactors, from: usize, to: usize| {
+        let (irradiance, albedo, emissivity) = &optics[from];
+        let (_, albedo_receiver, emissivity_receiver) = &optics[to];
+        crate::tpm::heating::mutual_heating(
+            vf,
+            irradiance.view(),
+            body_states[from].interior.t.column(0),
+            albedo.view(),
+            emissivity.view(),
+            albedo_receiver.view(),
+            emissivity_receiver.view(),
         )
+    };
+
+    let mut fluxes: Vec<_> = bodies
+        .iter()
+        .map(|body| Array1::zeros(body.mesh.facets.len()))
+        .collect();
+    for a in 0..bodies.len() {
+        for b in a + 1..bodies.len() {
+            let trans_b2a = body_states[a].state.inverse() * body_states[b].state;
+            let (vf_ab, vf_ba) = crate::tpm::heating::view_factor_matrix_bodies(
+                &bodies[a].mesh,
+                &bodies[b].mesh,
+                &trans_b2a,
+                params.occlusion,
+                params.cutoff,
+                threads,
+            );
+            fluxes[a] += &absorbed(&vf_ab, b, a);
+            fluxes[b] += &absorbed(&vf_ba, a, b);
+        }
+    }
+    fluxes
+}
+
+fn facet_error(e: SurfaceTemperatureError, facet: usize) -> SurfaceTemperatureError {
+    SurfaceTemperatureError {
+        facet: Some(facet),
+        ..e
     }
 }
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use ndarray::{Array1, Array2};
+
+use crate::{Float, Mat4, tpm::column::Interior};
+
+// Depth grid shared by all columns using the same thermal properties.
+// See tpm::column::depth_stencil.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub z: Array1<Float>,
+    pub dtpa: Array1<Float>,
+    pub dtpb: Array1<Float>,
+    pub g: [Float; 3],
+
+    // depth step above the last node (m)
+    pub dxb: Float,
+}
+
+impl Grid {
+    pub fn stencil(&self) -> crate::tpm::routine::Stencil<'_> {
+        crate::tpm::routine::Stencil::Nonuniform {
+            dtpa: self.dtpa.view(),
+            dtpb: self.dtpb.view(),
+            g: self.g,
+            dxb: self.dxb,
+        }
+    }
+
+    pub fn new(z: Array1<Float>, dt: Float) -> Self {
+        let (dtpa, dtpb, g) = crate::tpm::column::depth_stencil(z.view(), dt);
+        let n = z.len();
+        let dxb = z[n - 1] - z[n - 2];
+        Self {
+            z,
+            dtpa,
+            dtpb,
+            g,
+            dxb,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BodyRecord {
+    // facets recorded, index of the columns of the arrays below
+    pub facets: Vec<usize>,
+
+    // time since start of simulation (s)
+    pub time: Array1<Float>,
+
+    // (time, facet)
+    pub temperature_surface: Array2<Float>,
+
+    // (time, facet)
+    pub flux_surface: Array2<Float>,
+
+    // flux absorbed from the other bodies (time, facet)
+    pub flux_mutual: Array2<Float>,
+
+    // index of the record time where interior temperatures were saved
+    pub interior_time_indices: Vec<usize>,
+
+    // (interior time index)(facet)(depth)
+    pub temperature_interior: Vec<Vec<Array1<Float>>>,
+}
+
+impl BodyRecord {
+    pub fn new(facets: Vec<usize>, n: usize, surface: bool, flux: bool, mutual: bool) -> Self {
+        let nf = facets.len();
+        let shape = |b: bool| if b { (n, nf) } else { (0, nf) };
+        Self {
+            facets,
+            time: Array1::zeros(n),
+            temperature_surface: Array2::zeros(shape(surface)),
+            flux_surface: Array2::zeros(shape(flux)),
+            flux_mutual: Array2::zeros(shape(mutual)),
+            interior_time_indices: vec![],
+            temperature_interior: vec![],
+        }
+    }
+}
+
+impl std::fmt::Debug for BodyRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "BodyRecord(facets={}, time={}, temperature_surface={:?}, flux_surface={:?}, flux_mutual={:?}, interior_time_indices={:?})",
+            self.facets.len(),
+            self.time.len(),
+            self.temperature_surface.shape(),
+            self.flux_surface.shape(),
+            self.flux_mutual.shape(),
+            self.interior_time_indices,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct BodyState {
+    pub interior: Interior,
+
+    // index of thermal properties (defined in Setup) for each facet
+    pub properties: Vec<usize>,
+
+    // depth grids, by index of thermal properties
+    pub grids: HashMap<usize, Grid>,
+
+    // model matrix at current time (body-fixed frame to world frame)
+    pub state: Mat4,
+
+    // absorbed flux at surface at last step (W/m2)
+    pub flux: Array1<Float>,
+
+    // view factors between facets for self-heating, see tpm::heating::view_factor_matrix
+    pub view_factors: Option<crate::tpm::viewfactors::ViewFactors>,
+
+    // flux absorbed from the other bodies at last step, included in flux (W/m2)
+    pub flux_mutual: Array1<Float>,
+
+    // flux absorbed from the host planet at last step, included in flux (W/m2)
+    pub flux_planet: Array1<Float>,
+
+    // illuminated fraction of the facets at last step, see tpm::illumination
+    pub illumination: Array1<Float>,
+
+    pub record: BodyRecord,
+}
+
+impl std::fmt::Debug for BodyState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "BodyState(interior={}, grids={}, state={}, record={:?})",
+            self.interior.len(),
+            self.grids.len(),
+            &self.state,
+            self.record,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct State {
+    pub iteration: usize,
+
+    // number of iterations to reach total duration
+    pub iteration_total: usize,
+
+    // first iteration to be recorded
+    pub iteration_record: usize,
+
+    // time since start of simulation (s)
+    pub time: Float,
+
+    pub bodies: Vec<BodyState>,
+
+    // last progress percentage reached
+    pub progress: Float,
+
+    // rotations of spin-up and largest change of temperatures at the last one (K)
+    pub spinup_rotations: usize,
+    pub spinup_change: Float,
+}
+
+impl State {
+    pub fn new(iteration_total: usize, iteration_record: usize) -> Self {
+        Self {
+            iteration: 0,
+            iteration_total,
+            iteration_record,
+            time: 0.0,
+            bodies: vec![],
+            progress: Float::NEG_INFINITY,
+            spinup_rotations: 0,
+            spinup_change: 0.0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.iteration >= self.iteration_total
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.iteration >= self.iteration_record
+    }
+
+    pub fn iteration_recorded(&self) -> usize {
+        self.iteration - self.iteration_record
+    }
+}
+
+impl std::fmt::Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "State(iteration={}/{}, iteration_record={}, time={}, bodies={:?})",
+            self.iteration, self.iteration_total, self.iteration_record, self.time, self.bodies,
+        )
+    }
+}
@@ -0,0 +1,697 @@
+use anyhow::Result;
+use numpy::ndarray::{Array1, ArrayView1, ArrayViewMut1};
+
+use super::{
+    properties::Properties,
+    routine::{ColumnSolver, Nodes, Scheme, Stencil, Workspace},
+};
+use crate::Float;
+
+pub fn half_space_step(
+    z: ArrayView1<'_, Float>,
+    time: Float,
+    t_init: Float,
+    t_surface: Float,
+    d: Float,
+) -> Array1<Float> {
+    // Semi-infinite medium at t_init with the surface held at t_surface from time 0,
+    // Carslaw & Jaeger (1959) §2.4.
+    //
+    // z: depth (m)
+    // time: time since the step (s)
+    // t_init: initial temperature of the medium (K)
+    // t_surface: temperature of the surface (K)
+    // d: diffusivity (m2/s)
+    let s = 2.0 * (d * time).sqrt();
+    z.mapv(|z| t_surface + (t_init - t_surface) * crate::math::erf(z / s))
+}
+
+pub fn half_space_flux(
+    z: ArrayView1<'_, Float>,
+    time: Float,
+    t_init: Float,
+    q: Float,
+    k: Float,
+    d: Float,
+) -> Array1<Float> {
+    // Semi-infinite medium at t_init heated by a constant flux at the surface from time 0,
+    // Carslaw & Jaeger (1959) §2.9.
+    //
+    // q: flux entering the surface (W/m2)
+    // k: conductivity (W/m/K)
+    let s = 2.0 * (d * time).sqrt();
+    z.mapv(|z| {
+        let x = z / s;
+        let ierfc = (-x * x).exp() / crate::util::PI.sqrt() - x * crate::math::erfc(x);
+        t_init + q * s / k * ierfc
+    })
+}
+
+pub fn periodic_surface(
+    z: ArrayView1<'_, Float>,
+    time: Float,
+    t_mean: Float,
+    amplitude: Float,
+    period: Float,
+    d: Float,
+) -> Array1<Float> {
+    // Steady periodic regime of a semi-infinite medium with surface temperature
+    // t_mean + amplitude * cos(2 pi time / period), Carslaw & Jaeger (1959) §2.6.
+    //
+    // amplitude: amplitude of the surface temperature (K)
+    // period: period of the surface temperature (s)
+    let w = 2.0 * crate::util::PI / period;
+    let depth = (2.0 * d / w).sqrt();
+    z.mapv(|z| t_mean + amplitude * (-z / depth).exp() * (w * time - z / depth).cos())
+}
+
+pub fn periodic_radiation(
+    z: ArrayView1<'_, Float>,
+    time: Float,
+    f_mean: Float,
+    f_amplitude: Float,
+    period: Float,
+    prop: &Properties,
+) -> Array1<Float> {
+    // Steady periodic regime of a semi-infinite medium absorbing the flux
+    // f_mean + f_amplitude * cos(2 pi time / period) and radiating, with the emission linearised
+    // around the equilibrium temperature of f_mean. Valid for f_amplitude << f_mean.
+    //
+    // f_mean: mean absorbed flux (W/m2)
+    // f_amplitude: amplitude of the absorbed flux (W/m2)
+    // prop: emissivity, conductivity and diffusivity of the medium
+    let se = crate::util::STEFAN_BOLTZMANN * prop.emissivity;
+    let (k, d) = (prop.conductivity, prop.diffusivity);
+    let w = 2.0 * crate::util::PI / period;
+    let depth = (2.0 * d / w).sqrt();
+    let t_mean = (f_mean / se).powf(0.25);
+
+    // linearised emission (W/m2/K) and conduction (W/m2/K) of the surface
+    let h = 4.0 * se * t_mean.powi(3);
+    let c = k / depth;
+
+    let amplitude = f_amplitude / ((h + c).powi(2) + c.powi(2)).sqrt();
+    let lag = c.atan2(h + c);
+    z.mapv(|z| t_mean + amplitude * (-z / depth).exp() * (w * time - z / depth - lag).cos())
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ErrorNorms {
+    // mean absolute error (K)
+    pub l1: Float,
+
+    // root mean square error (K)
+    pub l2: Float,
+
+    // largest absolute error (K)
+    pub linf: Float,
+}
+
+impl ErrorNorms {
+    pub fn new(numerical: ArrayView1<'_, Float>, analytical: ArrayView1<'_, Float>) -> Self {
+        let n = numerical.len().max(1) as Float;
+        let e = &numerical - &analytical;
+        Self {
+            l1: e.fold(0.0, |a, &b| a + b.abs()) / n,
+            l2: (e.fold(0.0, |a, &b| a + b * b) / n).sqrt(),
+            linf: e.fold(0.0, |a: Float, &b| {
+                if b.abs() > a || b.is_nan() {
+                    b.abs()
+                } else {
+                    a
+                }
+            }),
+        }
+    }
+}
+
+// Problems with a closed-form solution to verify the solvers against.
+// Columns are uniform grids 10 skin depths deep (or 10 sqrt(d duration) for the step), started
+// from the initial or mean temperature.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reference {
+    // Surface held at t_surface, see half_space_step. Profiles are compared after the duration.
+    // The bottom is held at t_init.
+    HalfSpaceStep {
+        t_init: Float,
+        t_surface: Float,
+        duration: Float,
+    },
+
+    // Flux q absorbed at the surface without emission and surface temperature solved by
+    // update_thermal_state_inplace, see half_space_flux. Profiles are compared after the
+    // duration.
+    HalfSpaceFlux {
+        t_init: Float,
+        q: Float,
+        duration: Float,
+    },
+
+    // Surface temperature imposed, see periodic_surface. Profiles are compared after the
+    // periods, once the start from the mean temperature has faded out.
+    PeriodicSurface {
+        t_mean: Float,
+        amplitude: Float,
+        period: Float,
+        periods: usize,
+    },
+
+    // Absorbed flux imposed and surface temperature solved by update_thermal_state, see
+    // periodic_radiation. Surface temperatures are compared over the last period.
+    PeriodicRadiation {
+        f_mean: Float,
+        f_amplitude: Float,
+        period: Float,
+        periods: usize,
+    },
+}
+
+impl Reference {
+    // Depth grid of the numerical solution.
+    pub fn depth(&self, prop: &Properties, dx: Float) -> Result<Array1<Float>> {
+        let depth = match *self {
+            Self::HalfSpaceStep { duration, .. } | Self::HalfSpaceFlux { duration, .. } => {
+                (prop.diffusivity * duration).sqrt()
+            }
+            Self::PeriodicSurface { period, .. } | Self::PeriodicRadiation { period, .. } => {
+                super::properties::skin_depth_1(prop.diffusivity, period)
+            }
+        };
+        super::column::depth_uniform(10.0 * depth, dx)
+    }
+
+    // Error of the numerical solution with depth step dx and time step dt. Explicit scheme must
+    // respect the stability of the grid, see core::stability_maxdt.
+    pub fn verify(
+        &self,
+        prop: &Properties,
+        dx: Float,
+        dt: Float,
+        scheme: Scheme,
+    ) -> Result<ErrorNorms> {
+        let z = self.depth(prop, dx)?;
+        let d = Array1::from_elem(z.len(), prop.diffusivity);
+        let dtpdx2 = Array1::from_elem(z.len() - 2, dt / (dx * dx));
+
+        match *self {
+            Self::HalfSpaceStep {
+                t_init,
+                t_surface,
+                duration,
+            } => {
+                let n = (duration / dt).round() as usize;
+                let mut t = Array1::from_elem(z.len(), t_init);
+                for _ in 0..n {
+                    t = step_imposed(t, t_surface, d.view(), dtpdx2.view(), scheme);
+                }
+                let expected = half_space_step(
+                    z.view(),
+                    n as Float * dt,
+                    t_init,
+                    t_surface,
+                    prop.diffusivity,
+                );
+                Ok(ErrorNorms::new(t.view(), expected.view()))
+            }
+            Self::HalfSpaceFlux {
+                t_init,
+                q,
+                duration,
+            } => {
+                let n = (duration / dt).round() as usize;
+                let mut t = Array1::from_elem(z.len(), t_init);
+                let stencil = Stencil::Uniform {
+                    dtpdx2: dtpdx2.view(),
+                    twodx: 2.0 * dx,
+                };
+                step_flux(
+                    t.view_mut(),
+                    q,
+                    Nodes::Diffusivity(d.view()),
+                    prop,
+                    stencil,
+                    scheme,
+                    n,
+                )?;
+                let expected = half_space_flux(
+                    z.view(),
+                    n as Float * dt,
+                    t_init,
+                    q,
+                    prop.conductivity,
+                    prop.diffusivity,
+                );
+                Ok(ErrorNorms::new(t.view(), expected.view()))
+            }
+            Self::PeriodicSurface {
+                t_mean,
+                amplitude,
+                period,
+                periods,
+            } => {
+                let n = (periods as Float * period / dt).round() as usize;
+                let w = 2.0 * crate::util::PI / period;
+                let mut t = Array1::from_elem(z.len(), t_mean);
+                for it in 1..=n {
+                    let surface = t_mean + amplitude * (w * it as Float * dt).cos();
+                    t = step_imposed(t, surface, d.view(), dtpdx2.view(), scheme);
+                }
+                let expected = periodic_surface(
+                    z.view(),
+                    n as Float * dt,
+                    t_mean,
+                    amplitude,
+                    period,
+                    prop.diffusivity,
+                );
+                Ok(ErrorNorms::new(t.view(), expected.view()))
+            }
+            Self::PeriodicRadiation {
+                f_mean,
+                f_amplitude,
+                period,
+                periods,
+            } => {
+                let se = crate::util::STEFAN_BOLTZMANN * prop.emissivity;
+                let n_period = (period / dt).round() as usize;
+                let n = periods.max(1) * n_period;
+                let w = 2.0 * crate::util::PI / period;
+                let mut t = Array1::from_elem(z.len(), (f_mean / se).powf(0.25));
+                let mut numerical = Array1::zeros(n_period);
+                let mut expected = Array1::zeros(n_period);
+                for it in 1..=n {
+                    let time = it as Float * dt;
+                    t = super::routine::update_thermal_state(
+                        t.view(),
+                        f_mean + f_amplitude * (w * time).cos(),
+                        d.view(),
+                        Stencil::Uniform {
+                            dtpdx2: dtpdx2.view(),
+                            twodx: 2.0 * dx,
+                        },
+                        se,
+                        prop.conductivity,
+                        ColumnSolver {
+                            scheme,
+                            ..Default::default()
+                        },
+                    )?;
+                    if it > n - n_period {
+                        let jj = it - (n - n_period) - 1;
+                        numerical[jj] = t[0];
+                        expected[jj] = periodic_radiation(
+                            z.slice(numpy::ndarray::s![..1]),
+                            time,
+                            f_mean,
+                            f_amplitude,
+                            period,
+                            prop,
+                        )[0];
+                    }
+                }
+                Ok(ErrorNorms::new(numerical.view(), expected.view()))
+            }
+        }
+    }
+
+    // Errors for each pair of depth step and time step (dx, dt).
+    pub fn convergence(
+        &self,
+        prop: &Properties,
+        steps: &[(Float, Float)],
+        scheme: Scheme,
+    ) -> Result<Vec<ErrorNorms>> {
+        steps
+            .iter()
+            .map(|&(dx, dt)| self.verify(prop, dx, dt, scheme))
+            .collect()
+    }
+}
+
+pub fn convergence_order(h: &[Float], e: &[Float]) -> Float {
+    // Order of convergence, slope of the least-squares fit of log(e) against log(h).
+    //
+    // h: steps (dx or dt)
+    // e: errors for each step, see ErrorNorms
+    let n = h.len() as Float;
+    let x: Vec<Float> = h.iter().map(|h| h.ln()).collect();
+    let y: Vec<Float> = e.iter().map(|e| e.ln()).collect();
+    let mx = x.iter().sum::<Float>() / n;
+    let my = y.iter().sum::<Float>() / n;
+    let sxy: Float = x.iter().zip(&y).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let sxx: Float = x.iter().map(|x| (x - mx) * (x - mx)).sum();
+    sxy / sxx
+}
+
+// Temperatures after a step with imposed surface and bottom temperatures, the bottom keeps its
+// initial temperature.
+fn step_imposed(
+    mut t: Array1<Float>,
+    surface: Float,
+    d: ArrayView1<'_, Float>,
+    dtpdx2: ArrayView1<'_, Float>,
+    scheme: Scheme,
+) -> Array1<Float> {
+    let n = t.len();
+    let t_in = match scheme {
+        Scheme::Explicit => {
+            t[0] = surface;
+            super::core::conduction_1d(t.view(), d, dtpdx2)
+        }
+        Scheme::Implicit(theta) => super::core::conduction_1d_implicit(
+            t.view(),
+            d,
+            dtpdx2,
+            theta,
+            super::core::Boundaries {
+                surface: [surface, 0.0, 0.0],
+                bottom: (0.0, t[n - 1]),
+            },
+        ),
+    };
+    t[0] = surface;
+    t.slice_mut(numpy::ndarray::s![1..n - 1]).assign(&t_in);
+    t
+}
+
+// Temperatures after n steps absorbing the flux q at the surface without emission, through the
+// kernel of the simulations.
+fn step_flux(
+    mut t: ArrayViewMut1<'_, Float>,
+    q: Float,
+    nodes: Nodes<'_>,
+    prop: &Properties,
+    stencil: Stencil<'_>,
+    scheme: Scheme,
+    n: usize,
+) -> Result<()> {
+    let prop = Properties {
+        emissivity: 0.0,
+        ..prop.clone()
+    };
+    let solver = ColumnSolver {
+        scheme,
+        ..Default::default()
+    };
+    let mut work = Workspace::new();
+    for _ in 0..n {
+        super::routine::update_thermal_state_inplace(
+            t.view_mut(),
+            q,
+            nodes,
+            &prop,
+            stencil,
+            solver,
+            &mut work,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
+    use pyo3::prelude::*;
+
+    use crate::{Float, py::tpm::properties::Properties, tpm::routine::Scheme};
+
+    #[pyfunction]
+    pub fn half_space_step<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        time: Float,
+        t_init: Float,
+        t_surface: Float,
+        d: Float,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::half_space_step(z.as_array(), time, t_init, t_surface, d).to_pyarray(py)
+    }
+
+    #[pyfunction]
+    pub fn half_space_flux<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        time: Float,
+        t_init: Float,
+        q: Float,
+        k: Float,
+        d: Float,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::half_space_flux(z.as_array(), time, t_init, q, k, d).to_pyarray(py)
+    }
+
+    #[pyfunction]
+    pub fn periodic_surface<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        time: Float,
+        t_mean: Float,
+        amplitude: Float,
+        period: Float,
+        d: Float,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::periodic_surface(z.as_array(), time, t_mean, amplitude, period, d).to_pyarray(py)
+    }
+
+    #[pyfunction]
+    pub fn periodic_radiation<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        time: Float,
+        f_mean: Float,
+        f_amplitude: Float,
+        period: Float,
+        prop: Properties,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::periodic_radiation(
+            z.as_array(),
+            time,
+            f_mean,
+            f_amplitude,
+            period,
+            &prop.inner.borrow(),
+        )
+        .to_pyarray(py)
+    }
+
+    fn verify(
+        reference: super::Reference,
+        prop: Properties,
+        dx: Float,
+        dt: Float,
+        theta: Option<Float>,
+    ) -> PyResult<(Float, Float, Float)> {
+        let e = reference
+            .verify(&prop.inner.borrow(), dx, dt, Scheme::from_theta(theta))
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("{:#}", e)))?;
+        Ok((e.l1, e.l2, e.linf))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (prop, t_init, t_surface, duration, dx, dt, theta=None))]
+    pub fn verify_half_space_step(
+        prop: Properties,
+        t_init: Float,
+        t_surface: Float,
+        duration: Float,
+        dx: Float,
+        dt: Float,
+        theta: Option<Float>,
+    ) -> PyResult<(Float, Float, Float)> {
+        let reference = super::Reference::HalfSpaceStep {
+            t_init,
+            t_surface,
+            duration,
+        };
+        verify(reference, prop, dx, dt, theta)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (prop, t_init, q, duration, dx, dt, theta=None))]
+    pub fn verify_half_space_flux(
+        prop: Properties,
+        t_init: Float,
+        q: Float,
+        duration: Float,
+        dx: Float,
+        dt: Float,
+        theta: Option<Float>,
+    ) -> PyResult<(Float, Float, Float)> {
+        let reference = super::Reference::HalfSpaceFlux {
+            t_init,
+            q,
+            duration,
+        };
+        verify(reference, prop, dx, dt, theta)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (prop, t_mean, amplitude, period, periods, dx, dt, theta=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_periodic_surface(
+        prop: Properties,
+        t_mean: Float,
+        amplitude: Float,
+        period: Float,
+        periods: usize,
+        dx: Float,
+        dt: Float,
+        theta: Option<Float>,
+    ) -> PyResult<(Float, Float, Float)> {
+        let reference = super::Reference::PeriodicSurface {
+            t_mean,
+            amplitude,
+            period,
+            periods,
+        };
+        verify(reference, prop, dx, dt, theta)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (prop, f_mean, f_amplitude, period, periods, dx, dt, theta=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_periodic_radiation(
+        prop: Properties,
+        f_mean: Float,
+        f_amplitude: Float,
+        period: Float,
+        periods: usize,
+        dx: Float,
+        dt: Float,
+        theta: Option<Float>,
+    ) -> PyResult<(Float, Float, Float)> {
+        let reference = super::Reference::PeriodicRadiation {
+            f_mean,
+            f_amplitude,
+            period,
+            periods,
+        };
+        verify(reference, prop, dx, dt, theta)
+    }
+
+    #[pyfunction]
+    pub fn convergence_order(h: Vec<Float>, e: Vec<Float>) -> Float {
+        super::convergence_order(&h, &e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numpy::ndarray::Array1;
+
+    use super::*;
+    use crate::tpm::routine::Layers;
+
+    // Medium with a diffusivity of 1e-6 m2/s heated by 100 W/m2 for 1e4 s, about 11 K at the
+    // surface. Starts at 0 K so the errors are not lost in the rounding of the temperatures.
+    const Q: Float = 100.0;
+    const DURATION: Float = 1e4;
+    const T_INIT: Float = 0.0;
+
+    // Time steps are proportional to the square of the depth steps, the errors of the surface
+    // temperature solved with the nodes of the previous step are then second order in depth.
+    const SCHEMES: [Scheme; 3] = [
+        Scheme::Explicit,
+        Scheme::Implicit(0.5),
+        Scheme::Implicit(1.0),
+    ];
+
+    fn properties() -> Properties {
+        Properties::new(0.0, 0.9, 1000.0, 1000.0, 1000.0, 1.0, 1e-6)
+    }
+
+    // Error of a column of n steps over 1 m growing exponentially with depth, through the
+    // non-uniform stencil with diffusivity or layered nodes.
+    fn error_stretched(n: usize, layered: bool, scheme: Scheme) -> Float {
+        let prop = properties();
+        let a: Float = 2.0;
+        let z = Array1::from_shape_fn(n + 1, |ii| {
+            ((a * ii as Float / n as Float).exp() - 1.0) / (a.exp() - 1.0)
+        });
+        let dt = 0.25 * (z[1] - z[0]).powi(2) / prop.diffusivity;
+        let steps = (DURATION / dt).round() as usize;
+        let (dtpa, dtpb, g) = crate::tpm::column::depth_stencil(z.view(), dt);
+        let stencil = Stencil::Nonuniform {
+            dtpa: dtpa.view(),
+            dtpb: dtpb.view(),
+            g,
+            dxb: z[n] - z[n - 1],
+        };
+
+        let d = Array1::from_elem(n + 1, prop.diffusivity);
+        let p = Array1::from_elem(n + 1, prop.density);
+        let c = Array1::from_elem(n + 1, prop.heat_capacity);
+        let k = Array1::from_elem(n + 1, prop.conductivity);
+        let nodes = if layered {
+            Nodes::Layered(Layers {
+                p: p.view(),
+                c: c.view(),
+                k: k.view(),
+            })
+        } else {
+            Nodes::Diffusivity(d.view())
+        };
+
+        let mut t = Array1::from_elem(n + 1, T_INIT);
+        step_flux(t.view_mut(), Q, nodes, &prop, stencil, scheme, steps).unwrap();
+        let expected = half_space_flux(
+            z.view(),
+            steps as Float * dt,
+            T_INIT,
+            Q,
+            prop.conductivity,
+            prop.diffusivity,
+        );
+        ErrorNorms::new(t.view(), expected.view()).l2
+    }
+
+    fn order_stretched(layered: bool, scheme: Scheme) -> Float {
+        let n = [25, 50, 100];
+        let h: Vec<Float> = n.iter().map(|&n| 1.0 / n as Float).collect();
+        let e: Vec<Float> = n
+            .iter()
+            .map(|&n| error_stretched(n, layered, scheme))
+            .collect();
+        convergence_order(&h, &e)
+    }
+
+    #[test]
+    fn half_space_flux_uniform() {
+        let prop = properties();
+        let reference = Reference::HalfSpaceFlux {
+            t_init: T_INIT,
+            q: Q,
+            duration: DURATION,
+        };
+        let dx = [0.02, 0.01, 0.005];
+        let steps: Vec<_> = dx
+            .iter()
+            .map(|&dx| (dx, 0.25 * dx * dx / prop.diffusivity))
+            .collect();
+        for scheme in SCHEMES {
+            let e: Vec<Float> = reference
+                .convergence(&prop, &steps, scheme)
+                .unwrap()
+                .iter()
+                .map(|e| e.l2)
+                .collect();
+            let order = convergence_order(&dx, &e);
+            assert!(order > 1.7, "{:?}: order {}", scheme, order);
+        }
+    }
+
+    #[test]
+    fn half_space_flux_nonuniform() {
+        for scheme in SCHEMES {
+            let order = order_stretched(false, scheme);
+            assert!(order > 1.7, "{:?}: order {}", scheme, order);
+        }
+    }
+
+    #[test]
+    fn half_space_flux_layered() {
+        for scheme in SCHEMES {
+            let order = order_stretched(true, scheme);
+            assert!(order > 1.7, "{:?}: order {}", scheme, order);
+        }
+    }
+}
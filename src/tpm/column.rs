@@ -1,11 +1,20 @@
 use crate::Float;
-use numpy::ndarray::Array1;
+use anyhow::{Result, anyhow};
+use numpy::ndarray::{Array1, Array2, ArrayView1, Zip, s};
+
+use super::properties::Properties;
 
 #[derive(Clone, Debug, Default)]
 pub struct Column {
     pub z: Array1<Float>,
     pub t: Array1<Float>,
     pub d: Array1<Float>,
+
+    // density, heat capacity and conductivity of each node, before temperature dependence of the
+    // models of the surface properties
+    pub p: Array1<Float>,
+    pub c: Array1<Float>,
+    pub k: Array1<Float>,
     // pub g1: Array1<Float>,
     // pub g2: Array1<Float>,
     // pub a: Array1<Float>,
@@ -13,15 +22,379 @@ pub struct Column {
 }
 
 impl Column {
-    pub fn new(z: Array1<Float>, prop: super::properties::Properties, t_init: Float) -> Self {
+    pub fn new(z: Array1<Float>, prop: Properties, t_init: Float) -> Self {
+        Self::new_layered(z, &prop, &Layering::Homogeneous, t_init)
+    }
+
+    // Column with properties varying with depth, prop being the properties at surface.
+    pub fn new_layered(
+        z: Array1<Float>,
+        prop: &Properties,
+        layering: &Layering,
+        t_init: Float,
+    ) -> Self {
         let mut t = z.clone();
         t.fill(t_init);
 
-        let mut d = z.clone();
-        d.fill(prop.diffusivity);
+        let (p, c, k) = match layering {
+            Layering::Homogeneous => (
+                Array1::from_elem(z.len(), prop.density),
+                Array1::from_elem(z.len(), prop.heat_capacity),
+                Array1::from_elem(z.len(), prop.conductivity),
+            ),
+            Layering::Mantle {
+                thickness,
+                substrate,
+            } => {
+                let select = |a: Float, b: Float| z.mapv(|x| if x < *thickness { a } else { b });
+                (
+                    select(prop.density, substrate.density),
+                    select(prop.heat_capacity, substrate.heat_capacity),
+                    select(prop.conductivity, substrate.conductivity),
+                )
+            }
+            Layering::Transition { h, substrate } => {
+                let transition = |a: Float, b: Float| transition_layers(a, b, *h, z.view());
+                (
+                    transition(prop.density, substrate.density),
+                    transition(prop.heat_capacity, substrate.heat_capacity),
+                    transition(prop.conductivity, substrate.conductivity),
+                )
+            }
+        };
+
+        let d = if layering.is_homogeneous() {
+            Array1::from_elem(z.len(), prop.diffusivity)
+        } else {
+            &k / (&p * &c)
+        };
+
+        Self { z, t, d, p, c, k }
+    }
+
+    // Diffusivity of each node at current temperatures with the models of the surface
+    // properties.
+    pub fn diffusivity_at(&self, prop: &Properties) -> Array1<Float> {
+        if !prop.is_temperature_dependent() {
+            return self.d.clone();
+        }
+        diffusivity_nodes(
+            self.t.view(),
+            self.p.view(),
+            self.c.view(),
+            self.k.view(),
+            prop,
+        )
+    }
+}
+
+pub fn diffusivity_nodes(
+    t: ArrayView1<'_, Float>,
+    p: ArrayView1<'_, Float>,
+    c: ArrayView1<'_, Float>,
+    k: ArrayView1<'_, Float>,
+    prop: &Properties,
+) -> Array1<Float> {
+    // Diffusivity of each node at temperatures with the models of the surface properties.
+    //
+    // p, c, k: density, heat capacity and conductivity of each node
+    let kmodel = &prop.conductivity_model;
+    let cmodel = &prop.heat_capacity_model;
+    Zip::from(&t)
+        .and(&p)
+        .and(&c)
+        .and(&k)
+        .map_collect(|&t, &p, &c, &k| {
+            super::properties::diffusivity(kmodel.conductivity(k, t), p, cmodel.heat_capacity(c, t))
+        })
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum Layering {
+    // Properties of the surface at all depths.
+    #[default]
+    Homogeneous,
+
+    // Properties of the surface down to thickness (m), properties of the substrate below.
+    Mantle {
+        thickness: Float,
+        substrate: Properties,
+    },
+
+    // Exponential transition from the surface to the substrate with the e-folding scale h (m),
+    // see transition_layers.
+    Transition {
+        h: Float,
+        substrate: Properties,
+    },
+}
+
+impl Layering {
+    pub fn is_homogeneous(&self) -> bool {
+        matches!(self, Self::Homogeneous)
+    }
+}
+
+pub fn stability_maxdt_column(
+    z: ArrayView1<'_, Float>,
+    d: ArrayView1<'_, Float>,
+    s: Float,
+) -> Float {
+    // Largest dt for conduction_1d_nonuniform to be stable on a depth grid, uniform or not.
+    // Same as stability_maxdt with dx2 = dx1 * dx2 at each node inside.
+    //
+    // z: depth grid (m)
+    // d: diffusivity of each node (...)
+    // s: stability coef
+    let dx = &z.slice(s![1..]) - &z.slice(s![..-1]);
+    let mut maxdt = Float::INFINITY;
+    for ii in 0..dx.len() - 1 {
+        let dx2 = dx[ii] * dx[ii + 1];
+        maxdt = maxdt.min(super::core::stability_maxdt(d[ii + 1], dx2, s));
+    }
+    maxdt
+}
+
+pub fn temperature_profile(
+    z: ArrayView1<'_, Float>,
+    t_surface: Float,
+    t_deep: Float,
+    skin_depth: Float,
+) -> Array1<Float> {
+    // Temperatures decaying from the surface to the deep temperature with the skin depth, used
+    // to initialise columns close to their periodic state.
+    //
+    // z: depth grid (m)
+    // t_surface: temperature at surface (K)
+    // t_deep: temperature at depth, from the diurnally averaged absorbed flux (K)
+    // skin_depth: e-folding scale of the diurnal wave, see properties::skin_depth_1 (m), the
+    // profile is uniform at t_deep if not positive, e.g. non-rotating body or zero diffusivity
+    if skin_depth <= 0.0 || !skin_depth.is_finite() {
+        return Array1::from_elem(z.len(), t_deep);
+    }
+    z.mapv(|x| t_deep + (t_surface - t_deep) * (-x / skin_depth).exp())
+}
+
+pub fn transition_layers(
+    p1: Float,
+    p2: Float,
+    h: Float,
+    z: ArrayView1<'_, Float>,
+) -> Array1<Float> {
+    // Change in a property over depth between two layers, H-parameter of heat1d.
+    // h = 0.07 m for the Moon.
+    //
+    // p1: property of the upper layer
+    // p2: property of the deeper layer
+    // h: e-folding scale of the property (m)
+    // z: depth grid (m)
+    z.mapv(|x| p2 - (p2 - p1) * (-x / h).exp())
+}
+
+// Columns of all facets of a body stored contiguously as (facet, depth). Rows of facets with a
+// shorter depth grid are padded with the value of their last node.
+#[derive(Clone, Debug, Default)]
+pub struct Interior {
+    pub t: Array2<Float>,
+    pub d: Array2<Float>,
+    pub p: Array2<Float>,
+    pub c: Array2<Float>,
+    pub k: Array2<Float>,
+
+    // number of nodes of the column of each facet
+    pub sizes: Vec<usize>,
+}
+
+impl Interior {
+    pub fn from_columns(columns: &[Column]) -> Self {
+        let n = columns.iter().map(|c| c.t.len()).max().unwrap_or(0);
+        let store = |get: fn(&Column) -> &Array1<Float>| {
+            let mut a = Array2::zeros((columns.len(), n));
+            for (mut row, column) in a.outer_iter_mut().zip(columns) {
+                let v = get(column);
+                let m = v.len();
+                row.slice_mut(s![..m]).assign(v);
+                row.slice_mut(s![m..])
+                    .fill(v.last().copied().unwrap_or(0.0));
+            }
+            a
+        };
+        Self {
+            t: store(|c| &c.t),
+            d: store(|c| &c.d),
+            p: store(|c| &c.p),
+            c: store(|c| &c.c),
+            k: store(|c| &c.k),
+            sizes: columns.iter().map(|c| c.t.len()).collect(),
+        }
+    }
+
+    // Number of facets.
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
 
-        Self { z, t, d }
+    // Temperatures of the column of a facet, without padding.
+    pub fn t(&self, facet: usize) -> ArrayView1<'_, Float> {
+        self.t.slice(s![facet, ..self.sizes[facet]])
     }
 }
 
-pub type Interior = Vec<Column>;
+fn check_depth_grid(depth_max: Float, dx: Float) -> Result<()> {
+    // At least three nodes, the surface and two nodes below for the gradient at surface.
+    if dx <= 0.0 || !dx.is_finite() {
+        return Err(anyhow!(
+            "Depth step must be positive and finite, got {}",
+            dx
+        ));
+    }
+    if depth_max <= 0.0 || !depth_max.is_finite() {
+        return Err(anyhow!(
+            "Depth to reach must be positive and finite, got {}",
+            depth_max
+        ));
+    }
+    if depth_max <= dx {
+        return Err(anyhow!(
+            "Depth to reach ({}) must be greater than the depth step ({})",
+            depth_max,
+            dx
+        ));
+    }
+    Ok(())
+}
+
+pub fn depth_uniform(depth_max: Float, dx: Float) -> Result<Array1<Float>> {
+    // Depth grid with constant step, the last node is at or below depth_max.
+    //
+    // depth_max: depth to reach (m)
+    // dx: depth step (m)
+    check_depth_grid(depth_max, dx)?;
+    Ok(Array1::range(0.0, depth_max + dx, dx))
+}
+
+pub fn depth_geometric(depth_max: Float, dx: Float, n: Float) -> Result<Array1<Float>> {
+    // Depth grid with step increasing geometrically, as in heat1d.
+    // dx[i] = dx[i-1] * (1 + 1 / n), the last node is at or below depth_max.
+    //
+    // depth_max: depth to reach (m)
+    // dx: first depth step (m)
+    // n: growth parameter of the depth step, positive
+    check_depth_grid(depth_max, dx)?;
+    if n <= 0.0 || n.is_nan() {
+        return Err(anyhow!("Growth parameter must be positive, got {}", n));
+    }
+    let mut z = vec![0.0];
+    let mut h = dx;
+    while z[z.len() - 1] < depth_max {
+        z.push(z[z.len() - 1] + h);
+        h *= 1.0 + 1.0 / n;
+    }
+    Ok(Array1::from_vec(z))
+}
+
+pub fn depth_stencil(
+    z: ArrayView1<'_, Float>,
+    dt: Float,
+) -> (Array1<Float>, Array1<Float>, [Float; 3]) {
+    // Finite difference coefficients of a depth grid, uniform or not, used by
+    // conduction_1d_nonuniform and newton_method_gradient.
+    //
+    // z: depth grid (m)
+    // dt: time step (s)
+    //
+    // output dt * a and dt * b of each node inside, and gradient coefficients at surface
+    let dx = &z.slice(s![1..]) - &z.slice(s![..-1]);
+    let n = dx.len() - 1;
+    let mut dtpa = Array1::zeros(n);
+    let mut dtpb = Array1::zeros(n);
+    for ii in 0..n {
+        let (a, b) = super::core::depth_steps_nonuniform(dx[ii], dx[ii + 1]);
+        dtpa[ii] = dt * a;
+        dtpb[ii] = dt * b;
+    }
+    let g = super::core::surface_gradient(dx[0], dx[1]);
+    (dtpa, dtpb, g)
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
+    use pyo3::exceptions::PyValueError;
+    use pyo3::prelude::*;
+
+    use crate::Float;
+
+    #[pyfunction]
+    pub fn depth_uniform<'py>(
+        py: Python<'py>,
+        depth_max: Float,
+        dx: Float,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let z = super::depth_uniform(depth_max, dx)
+            .map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+        Ok(z.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (depth_max, dx, n=5.0))]
+    pub fn depth_geometric<'py>(
+        py: Python<'py>,
+        depth_max: Float,
+        dx: Float,
+        n: Float,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let z = super::depth_geometric(depth_max, dx, n)
+            .map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+        Ok(z.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    pub fn depth_stencil<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        dt: Float,
+    ) -> (
+        Bound<'py, PyArray1<Float>>,
+        Bound<'py, PyArray1<Float>>,
+        [Float; 3],
+    ) {
+        let (dtpa, dtpb, g) = super::depth_stencil(z.as_array(), dt);
+        (dtpa.to_pyarray(py), dtpb.to_pyarray(py), g)
+    }
+
+    #[pyfunction]
+    pub fn transition_layers<'py>(
+        py: Python<'py>,
+        p1: Float,
+        p2: Float,
+        h: Float,
+        z: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::transition_layers(p1, p2, h, z.as_array()).to_pyarray(py)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (z, d, s=0.5))]
+    pub fn stability_maxdt_column<'py>(
+        z: PyReadonlyArray1<'py, Float>,
+        d: PyReadonlyArray1<'py, Float>,
+        s: Float,
+    ) -> Float {
+        super::stability_maxdt_column(z.as_array(), d.as_array(), s)
+    }
+
+    #[pyfunction]
+    pub fn temperature_profile<'py>(
+        py: Python<'py>,
+        z: PyReadonlyArray1<'py, Float>,
+        t_surface: Float,
+        t_deep: Float,
+        skin_depth: Float,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::temperature_profile(z.as_array(), t_surface, t_deep, skin_depth).to_pyarray(py)
+    }
+}
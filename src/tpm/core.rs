@@ -57,7 +57,7 @@
 //     Required to obtain thermal equilibrium and essential to derive Planck spectrum.
 //     a = 1 - e
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use numpy::ndarray::{Array1, ArrayView1, s};
 use pyo3::prelude::*;
 
@@ -161,8 +161,63 @@ pub fn newton_method_dfn(set3: Float, k: Float, twodx: Float) -> Float {
     -4.0 * set3 - 3.0 * k / twodx
 }
 
+#[pyfunction]
+pub fn surface_gradient(dx1: Float, dx2: Float) -> [Float; 3] {
+    // Coefficients of the one-sided second order temperature gradient at the surface, such as
+    // dT/dz = g[0] * t + g[1] * subt1 + g[2] * subt2.
+    // Uniform grid is g = [-3, 4, -1] / twodx.
+    //
+    // dx1: depth step between surface and first node (m)
+    // dx2: depth step between first and second node (m)
+    let dx12 = dx1 + dx2;
+    [
+        -(2.0 * dx1 + dx2) / (dx1 * dx12),
+        dx12 / (dx1 * dx2),
+        -dx1 / (dx2 * dx12),
+    ]
+}
+
+pub fn newton_method_gradient(
+    t: Float,
+    f: Float,
+    se: Float,
+    k: Float,
+    subt1: Float,
+    subt2: Float,
+    g: [Float; 3],
+) -> Result<Float> {
+    // Same as newton_method for non-uniform depth grids, see surface_gradient.
+    newton_method_conductivity(t, f, se, |_| (k, 0.0), subt1, subt2, g)
+}
+
+pub fn newton_method_conductivity<F>(
+    t: Float,
+    f: Float,
+    se: Float,
+    k: F,
+    subt1: Float,
+    subt2: Float,
+    g: [Float; 3],
+) -> Result<Float>
+where
+    F: Fn(Float) -> (Float, Float),
+{
+    // Same as newton_method_gradient for temperature-dependent conductivity.
+    //
+    // k: conductivity and its derivative with temperature at surface
+    Ok(surface_temperature(
+        [t, subt1, subt2],
+        f,
+        se,
+        k,
+        g,
+        crate::util::NEWTON_METHOD_THRESHOLD,
+        crate::util::NEWTON_METHOD_MAX_ITERATION,
+    )?)
+}
+
 pub fn newton_method(
-    mut t: Float,
+    t: Float,
     f: Float,
     se: Float,
     k: Float,
@@ -170,19 +225,159 @@ pub fn newton_method(
     subt2: Float,
     twodx: Float,
 ) -> Result<Float> {
-    for _ in 0..crate::util::NEWTON_METHOD_MAX_ITERATION {
-        let set3 = se * t.powi(3);
-        let fn_ = newton_method_fn(t, f, set3, k, subt1, subt2, twodx);
-        let dfn = newton_method_dfn(set3, k, twodx);
-        let delta = -fn_ / dfn;
-        t += delta;
-        if delta.abs() < crate::util::NEWTON_METHOD_THRESHOLD {
-            return Ok(t);
+    let g = [-3.0 / twodx, 4.0 / twodx, -1.0 / twodx];
+    newton_method_gradient(t, f, se, k, subt1, subt2, g)
+}
+
+pub fn surface_temperature<F>(
+    t: [Float; 3],
+    f: Float,
+    se: Float,
+    k: F,
+    g: [Float; 3],
+    tolerance: Float,
+    max_iteration: usize,
+) -> Result<Float, SurfaceTemperatureError>
+where
+    F: Fn(Float) -> (Float, Float),
+{
+    // Surface temperature balancing absorbed flux, emission and conduction, see
+    // newton_method_conductivity, solved with newton_bisection.
+    //
+    // t: temperatures at surface and of the two nodes below at previous step (K)
+    // tolerance: convergence on temperature (K)
+    let [t, subt1, subt2] = t;
+    let energy = |x: Float| {
+        let set3 = se * x.powi(3);
+        let (kx, dkx) = k(x);
+        let gradient = g[0] * x + g[1] * subt1 + g[2] * subt2;
+        (
+            f - set3 * x + kx * gradient,
+            -4.0 * set3 + dkx * gradient + kx * g[0],
+        )
+    };
+    newton_bisection(energy, t, tolerance, max_iteration).map_err(|estimate| {
+        SurfaceTemperatureError {
+            facet: None,
+            flux: f,
+            temperature: t,
+            estimate,
+        }
+    })
+}
+
+pub fn surface_linear(t: [Float; 3], se: Float, k: (Float, Float), g: [Float; 3]) -> [Float; 3] {
+    // Energy balance of surface_temperature linearised around its solution, as the surface
+    // temperature of the new step s[0] + s[1] * t1 + s[2] * t2 of the two nodes below at the
+    // new step, for implicit schemes to conduct the flux balanced at surface.
+    //
+    // t: surface temperature solution and temperatures of the two nodes below it was solved
+    // with (K)
+    // k: conductivity at surface and its derivative with temperature
+    let [t, subt1, subt2] = t;
+    let gradient = g[0] * t + g[1] * subt1 + g[2] * subt2;
+    let derivative = -4.0 * se * t.powi(3) + k.1 * gradient + k.0 * g[0];
+    let s1 = -k.0 * g[1] / derivative;
+    let s2 = -k.0 * g[2] / derivative;
+    [t - s1 * subt1 - s2 * subt2, s1, s2]
+}
+
+pub fn newton_bisection<F>(
+    func: F,
+    x0: Float,
+    tolerance: Float,
+    max_iteration: usize,
+) -> Result<Float, Float>
+where
+    F: Fn(Float) -> (Float, Float),
+{
+    // Root of a function decreasing from positive values at zero, such as the energy balance at
+    // surface. Newton steps are kept inside a bracket of the root, bisection is used when a step
+    // leaves the bracket or does not reduce the bracket enough.
+    // Error is the last estimate when the root is not found.
+    //
+    // func: value and derivative of the function
+    // x0: first estimate
+    // tolerance: convergence on x
+    let mut lo = 0.0;
+    let flo = func(lo).0;
+    if flo == 0.0 {
+        return Ok(lo);
+    } else if flo.is_nan() || flo < 0.0 {
+        return Err(lo);
+    }
+
+    let mut hi = x0.max(1.0);
+    let mut ii = 0;
+    while func(hi).0 > 0.0 {
+        lo = hi;
+        hi *= 2.0;
+        ii += 1;
+        if ii >= max_iteration || !hi.is_finite() {
+            return Err(hi);
+        }
+    }
+
+    let mut x = x0.clamp(lo, hi);
+    let mut step = hi - lo;
+    for _ in 0..max_iteration {
+        let (fx, dfx) = func(x);
+        if fx == 0.0 {
+            return Ok(x);
+        } else if fx > 0.0 {
+            lo = x;
+        } else {
+            hi = x;
+        }
+
+        let newton = x - fx / dfx;
+        let next = if newton > lo && newton < hi && (newton - x).abs() < 0.5 * step {
+            newton
+        } else {
+            0.5 * (lo + hi)
+        };
+        step = (next - x).abs();
+        x = next;
+
+        if step < tolerance {
+            return Ok(x);
+        }
+    }
+    Err(x)
+}
+
+// Failure of the surface temperature solver.
+#[derive(Clone, Debug)]
+pub struct SurfaceTemperatureError {
+    // index of the facet, set by the routine or driver when known
+    pub facet: Option<usize>,
+
+    // absorbed flux at surface (W/m2)
+    pub flux: Float,
+
+    // temperature at surface at previous step (K)
+    pub temperature: Float,
+
+    // last estimate of the solver (K)
+    pub estimate: Float,
+}
+
+impl std::fmt::Display for SurfaceTemperatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Surface temperature did not converge (")?;
+        if let Some(facet) = self.facet {
+            write!(f, "facet={}, ", facet)?;
         }
+        write!(
+            f,
+            "flux={}, previous temperature={}, estimate={})",
+            self.flux, self.temperature, self.estimate
+        )
     }
-    Err(anyhow!("Newton method reached maximum iteration"))
 }
 
+impl std::error::Error for SurfaceTemperatureError {}
+
 pub fn conduction_1d(
     t: ArrayView1<'_, Float>,
     d: ArrayView1<'_, Float>,
@@ -192,6 +387,163 @@ pub fn conduction_1d(
     &t_mid + &d.slice(s![1..-1]) * &dtpdx2 * (&t.slice(s![..-2]) - 2.0 * &t_mid + &t.slice(s![2..]))
 }
 
+// Nodes at both ends of the new step of implicit schemes, as linear functions of the nodes
+// inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boundaries {
+    // surface as surface[0] + surface[1] * t[1] + surface[2] * t[2], [x, 0, 0] imposes x, see
+    // surface_linear
+    pub surface: [Float; 3],
+
+    // last node as bottom.0 * node above + bottom.1, (1, 0) adiabatic, see
+    // tpm::routine::BottomBoundary::linear
+    pub bottom: (Float, Float),
+}
+
+pub fn conduction_1d_implicit(
+    t: ArrayView1<'_, Float>,
+    d: ArrayView1<'_, Float>,
+    dtpdx2: ArrayView1<'_, Float>,
+    theta: Float,
+    boundaries: Boundaries,
+) -> Array1<Float> {
+    // Theta scheme of conduction_1d, unconditionally stable for theta >= 0.5.
+    // Same inputs and output as conduction_1d, t[0] is the surface temperature of the previous
+    // step.
+    //
+    // theta: 0 explicit, 0.5 Crank-Nicolson, 1 backward Euler
+    conduction_1d_implicit_nonuniform(t, d, dtpdx2, dtpdx2, theta, boundaries)
+}
+
+#[pyfunction]
+pub fn depth_steps_nonuniform(dx1: Float, dx2: Float) -> (Float, Float) {
+    // Coefficients of the second derivative at a node between two depth steps, such as
+    // d2T/dz2 = a * subt_above - (a + b) * t + b * subt_below.
+    // Uniform grid is a = b = 1 / dx2.
+    //
+    // dx1: depth step above the node (m)
+    // dx2: depth step below the node (m)
+    let dx12 = dx1 + dx2;
+    (2.0 / (dx1 * dx12), 2.0 / (dx2 * dx12))
+}
+
+pub fn conduction_1d_nonuniform(
+    t: ArrayView1<'_, Float>,
+    d: ArrayView1<'_, Float>,
+    dtpa: ArrayView1<'_, Float>,
+    dtpb: ArrayView1<'_, Float>,
+) -> Array1<Float> {
+    // Same as conduction_1d for non-uniform depth grids, see depth_steps_nonuniform.
+    //
+    // dtpa: dt * a of each node inside (s/m2)
+    // dtpb: dt * b of each node inside (s/m2)
+    let n = t.len();
+    let t_mid = t.slice(s![1..n - 1]);
+    &t_mid
+        + &d.slice(s![1..n - 1])
+            * (&dtpa * &t.slice(s![..n - 2]) - (&dtpa + &dtpb) * t_mid + &dtpb * &t.slice(s![2..]))
+}
+
+pub fn conduction_1d_variable(
+    t: ArrayView1<'_, Float>,
+    k: ArrayView1<'_, Float>,
+    pc: ArrayView1<'_, Float>,
+    dtpa: ArrayView1<'_, Float>,
+    dtpb: ArrayView1<'_, Float>,
+) -> Array1<Float> {
+    // Same as conduction_1d_nonuniform with conductivity and heat capacity varying with depth or
+    // temperature. Flux form conserving energy across layers, conductivity at the interfaces
+    // between nodes is the harmonic mean of nodes.
+    //
+    // k: conductivity of each node (...)
+    // pc: density times heat capacity of each node (...)
+    let (ka, kb) = conductivity_interfaces(k);
+    let n = t.len();
+    let t_mid = t.slice(s![1..n - 1]);
+    &t_mid
+        + (&dtpa * &ka * (&t.slice(s![..n - 2]) - &t_mid)
+            + &dtpb * &kb * (&t.slice(s![2..]) - &t_mid))
+            / pc.slice(s![1..n - 1])
+}
+
+pub fn conduction_1d_implicit_variable(
+    t: ArrayView1<'_, Float>,
+    k: ArrayView1<'_, Float>,
+    pc: ArrayView1<'_, Float>,
+    dtpa: ArrayView1<'_, Float>,
+    dtpb: ArrayView1<'_, Float>,
+    theta: Float,
+    boundaries: Boundaries,
+) -> Array1<Float> {
+    // Same as conduction_1d_implicit_nonuniform with conductivity and heat capacity varying with
+    // depth or temperature, see conduction_1d_variable. Coefficients are evaluated at the
+    // temperatures given in input.
+    let (ka, kb) = conductivity_interfaces(k);
+    let n = t.len() - 2;
+    let pc_mid = pc.slice(s![1..n + 1]);
+    let ra = &dtpa * &ka / pc_mid;
+    let rb = &dtpb * &kb / pc_mid;
+
+    let a = ra.mapv(|r| -theta * r);
+    let mut b = (&ra + &rb).mapv(|r| 1.0 + theta * r);
+    let mut c = rb.mapv(|r| -theta * r);
+
+    let t_mid = t.slice(s![1..n + 1]);
+    let mut rhs = &t_mid
+        + (1.0 - theta) * (&ra * &t.slice(s![..n]) - (&ra + &rb) * t_mid + &rb * &t.slice(s![2..]));
+    let Boundaries { surface, bottom } = boundaries;
+    b[0] += a[0] * surface[1];
+    c[0] += a[0] * surface[2];
+    rhs[0] -= a[0] * surface[0];
+    b[n - 1] += c[n - 1] * bottom.0;
+    rhs[n - 1] -= c[n - 1] * bottom.1;
+
+    crate::math::tridiagonal(a.view(), b.view(), c.view(), rhs.view())
+}
+
+fn conductivity_interfaces(k: ArrayView1<'_, Float>) -> (Array1<Float>, Array1<Float>) {
+    // Conductivity at interfaces above and below each node inside.
+    // Interfaces are halfway between nodes, so the two half steps are in series.
+    let n = k.len();
+    let (k1, k2) = (k.slice(s![..n - 1]), k.slice(s![1..]));
+    let kh = 2.0 * &k1 * k2 / (&k1 + &k2);
+    (
+        kh.slice(s![..n - 2]).to_owned(),
+        kh.slice(s![1..]).to_owned(),
+    )
+}
+
+pub fn conduction_1d_implicit_nonuniform(
+    t: ArrayView1<'_, Float>,
+    d: ArrayView1<'_, Float>,
+    dtpa: ArrayView1<'_, Float>,
+    dtpb: ArrayView1<'_, Float>,
+    theta: Float,
+    boundaries: Boundaries,
+) -> Array1<Float> {
+    // Same as conduction_1d_implicit for non-uniform depth grids, see depth_steps_nonuniform.
+    let n = t.len() - 2;
+    let d_mid = d.slice(s![1..n + 1]);
+    let ra = &d_mid * &dtpa;
+    let rb = &d_mid * &dtpb;
+
+    let a = ra.mapv(|r| -theta * r);
+    let mut b = (&ra + &rb).mapv(|r| 1.0 + theta * r);
+    let mut c = rb.mapv(|r| -theta * r);
+
+    let t_mid = t.slice(s![1..n + 1]);
+    let mut rhs = &t_mid
+        + (1.0 - theta) * (&ra * &t.slice(s![..n]) - (&ra + &rb) * t_mid + &rb * &t.slice(s![2..]));
+    let Boundaries { surface, bottom } = boundaries;
+    b[0] += a[0] * surface[1];
+    c[0] += a[0] * surface[2];
+    rhs[0] -= a[0] * surface[0];
+    b[n - 1] += c[n - 1] * bottom.0;
+    rhs[n - 1] -= c[n - 1] * bottom.1;
+
+    crate::math::tridiagonal(a.view(), b.view(), c.view(), rhs.view())
+}
+
 pub(crate) mod py {
     use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
     use pyo3::prelude::*;
@@ -208,7 +560,22 @@ pub(crate) mod py {
         subt2: Float,
         twodx: Float,
     ) -> PyResult<Float> {
-        Ok(super::newton_method(t, f, se, k, subt1, subt2, twodx).unwrap())
+        super::newton_method(t, f, se, k, subt1, subt2, twodx)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+
+    #[pyfunction]
+    pub fn newton_method_gradient(
+        t: Float,
+        f: Float,
+        se: Float,
+        k: Float,
+        subt1: Float,
+        subt2: Float,
+        g: [Float; 3],
+    ) -> PyResult<Float> {
+        super::newton_method_gradient(t, f, se, k, subt1, subt2, g)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
     }
 
     #[pyfunction]
@@ -220,4 +587,159 @@ pub(crate) mod py {
     ) -> Bound<'py, PyArray1<Float>> {
         super::conduction_1d(t.as_array(), d.as_array(), dtpdx2.as_array()).to_pyarray(py)
     }
+
+    fn check_column(t: usize, nodes: &[(&str, usize)], inside: &[(&str, usize)]) -> PyResult<()> {
+        // Column of at least 3 nodes, the arrays of nodes of the same length as t and the arrays
+        // of the nodes inside 2 shorter.
+        if t < 3 {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Expected a column of at least 3 nodes, got {}",
+                t
+            )));
+        }
+        let expected = nodes
+            .iter()
+            .map(|&(name, n)| (name, n, t))
+            .chain(inside.iter().map(|&(name, n)| (name, n, t - 2)));
+        for (name, n, m) in expected {
+            if n != m {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Expected {} of length {} for {} nodes, got {}",
+                    name, m, t, n
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, d, dtpdx2, theta=1.0, surface=None, bottom=(1.0, 0.0)))]
+    pub fn conduction_1d_implicit<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        d: PyReadonlyArray1<'py, Float>,
+        dtpdx2: PyReadonlyArray1<'_, Float>,
+        theta: Float,
+        surface: Option<[Float; 3]>,
+        bottom: (Float, Float),
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let (t, d, dtpdx2) = (t.as_array(), d.as_array(), dtpdx2.as_array());
+        check_column(t.len(), &[("d", d.len())], &[("dtpdx2", dtpdx2.len())])?;
+        Ok(super::conduction_1d_implicit(
+            t,
+            d,
+            dtpdx2,
+            theta,
+            super::Boundaries {
+                surface: surface.unwrap_or([t[0], 0.0, 0.0]),
+                bottom,
+            },
+        )
+        .to_pyarray(py))
+    }
+
+    #[pyfunction]
+    pub fn conduction_1d_nonuniform<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        d: PyReadonlyArray1<'py, Float>,
+        dtpa: PyReadonlyArray1<'_, Float>,
+        dtpb: PyReadonlyArray1<'_, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::conduction_1d_nonuniform(
+            t.as_array(),
+            d.as_array(),
+            dtpa.as_array(),
+            dtpb.as_array(),
+        )
+        .to_pyarray(py)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, d, dtpa, dtpb, theta=1.0, surface=None, bottom=(1.0, 0.0)))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn conduction_1d_implicit_nonuniform<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        d: PyReadonlyArray1<'py, Float>,
+        dtpa: PyReadonlyArray1<'_, Float>,
+        dtpb: PyReadonlyArray1<'_, Float>,
+        theta: Float,
+        surface: Option<[Float; 3]>,
+        bottom: (Float, Float),
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let (t, d, dtpa, dtpb) = (t.as_array(), d.as_array(), dtpa.as_array(), dtpb.as_array());
+        check_column(
+            t.len(),
+            &[("d", d.len())],
+            &[("dtpa", dtpa.len()), ("dtpb", dtpb.len())],
+        )?;
+        Ok(super::conduction_1d_implicit_nonuniform(
+            t,
+            d,
+            dtpa,
+            dtpb,
+            theta,
+            super::Boundaries {
+                surface: surface.unwrap_or([t[0], 0.0, 0.0]),
+                bottom,
+            },
+        )
+        .to_pyarray(py))
+    }
+
+    #[pyfunction]
+    pub fn conduction_1d_variable<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        k: PyReadonlyArray1<'py, Float>,
+        pc: PyReadonlyArray1<'py, Float>,
+        dtpa: PyReadonlyArray1<'_, Float>,
+        dtpb: PyReadonlyArray1<'_, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::conduction_1d_variable(
+            t.as_array(),
+            k.as_array(),
+            pc.as_array(),
+            dtpa.as_array(),
+            dtpb.as_array(),
+        )
+        .to_pyarray(py)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, k, pc, dtpa, dtpb, theta=1.0, surface=None, bottom=(1.0, 0.0)))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn conduction_1d_implicit_variable<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        k: PyReadonlyArray1<'py, Float>,
+        pc: PyReadonlyArray1<'py, Float>,
+        dtpa: PyReadonlyArray1<'_, Float>,
+        dtpb: PyReadonlyArray1<'_, Float>,
+        theta: Float,
+        surface: Option<[Float; 3]>,
+        bottom: (Float, Float),
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let (t, k, pc) = (t.as_array(), k.as_array(), pc.as_array());
+        let (dtpa, dtpb) = (dtpa.as_array(), dtpb.as_array());
+        check_column(
+            t.len(),
+            &[("k", k.len()), ("pc", pc.len())],
+            &[("dtpa", dtpa.len()), ("dtpb", dtpb.len())],
+        )?;
+        Ok(super::conduction_1d_implicit_variable(
+            t,
+            k,
+            pc,
+            dtpa,
+            dtpb,
+            theta,
+            super::Boundaries {
+                surface: surface.unwrap_or([t[0], 0.0, 0.0]),
+                bottom,
+            },
+        )
+        .to_pyarray(py))
+    }
 }
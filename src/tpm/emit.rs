@@ -1,7 +1,10 @@
-use numpy::ndarray::ArrayView1;
+use anyhow::{Result, anyhow};
+use numpy::ndarray::{Array, Array1, Array2, ArrayView, ArrayView1, ArrayView2, Dimension, Zip};
 use pyo3::prelude::*;
 
-use crate::Float;
+use super::illumination::Occluder;
+use super::response::{BandPlanck, SpectralResponse};
+use crate::{Float, Vec3, mesh::Mesh};
 
 #[pyfunction]
 pub fn planck(t: Float, w: Float) -> Float {
@@ -61,16 +64,189 @@ pub fn reflectance(w: Float, a: Float, area: Float, cose: Float, d: Float, r: Fl
     planck(crate::util::TEMP_SUN, w) * a * area * cose / (d * d * r * r)
 }
 
+#[pyfunction]
+pub fn jansky(f: Float, w: Float) -> Float {
+    // f: spectral flux density per wavelength (W/m3)
+    // w: wavelength (m)
+    //
+    // output spectral flux density per frequency (Jy)
+    f * w * w / crate::util::SPEED_LIGHT * crate::util::JANSKY
+}
+
+#[pyfunction]
+pub fn planck_inverse(f: Float, w: Float) -> Float {
+    // f: spectral radiance (W/m3/sr)
+    // w: wavelength (m)
+    //
+    // output temperature (K), 0 if the radiance is not above 0
+    if f <= 0.0 {
+        return 0.0;
+    }
+    crate::util::HC_PER_K / (w * (crate::util::TWO_HC2 / (w.powi(5) * f)).ln_1p())
+}
+
+#[pyfunction]
+#[pyo3(signature = (f, w, e=1.0, reflected=0.0))]
+pub fn brightness_temperature(f: Float, w: Float, e: Float, reflected: Float) -> Float {
+    // f: observed spectral radiance (W/m3/sr)
+    // w: wavelength (m)
+    // e: spectral emissivity, 1 for the brightness temperature
+    // reflected: sunlight reflected in the same unit as f, removed before inversion, see
+    // reflectance
+    //
+    // output temperature (K)
+    planck_inverse((f - reflected) / e, w)
+}
+
+pub fn brightness_temperature_array<D: Dimension>(
+    f: ArrayView<Float, D>,
+    w: Float,
+    e: ArrayView<Float, D>,
+    reflected: ArrayView<Float, D>,
+) -> Array<Float, D> {
+    // Temperatures of an image or of facets, see brightness_temperature. The arrays have the
+    // same shape, use ArrayView::broadcast for a value common to all.
+    Zip::from(&f)
+        .and(&e)
+        .and(&reflected)
+        .map_collect(|&f, &e, &r| brightness_temperature(f, w, e, r))
+}
+
+pub fn band_brightness_temperature(
+    f: Float,
+    table: &BandPlanck,
+    e: Float,
+    reflected: Float,
+) -> Float {
+    // f: observed band radiance through the response of the table (W/m3/sr)
+    // e: emissivity over the band
+    // reflected: sunlight reflected over the band in the same unit as f, see band_reflectance
+    //
+    // output temperature (K), clamped to the range of the table
+    table.temperature((f - reflected) / e)
+}
+
+pub fn band_brightness_temperature_array<D: Dimension>(
+    f: ArrayView<Float, D>,
+    table: &BandPlanck,
+    e: ArrayView<Float, D>,
+    reflected: ArrayView<Float, D>,
+) -> Array<Float, D> {
+    // Temperatures of an image or of facets, see band_brightness_temperature and
+    // brightness_temperature_array.
+    Zip::from(&f)
+        .and(&e)
+        .and(&reflected)
+        .map_collect(|&f, &e, &r| band_brightness_temperature(f, table, e, r))
+}
+
+pub fn band_reflectance(
+    response: &SpectralResponse,
+    a: Float,
+    area: Float,
+    cose: Float,
+    d: Float,
+    r: Float,
+) -> Float {
+    // Sunlight reflected averaged over a spectral response, see reflectance.
+    response.band_radiance(crate::util::TEMP_SUN) * a * area * cose / (d * d * r * r)
+}
+
+pub fn spectral_flux_density(
+    mesh: &Mesh,
+    t: ArrayView1<Float>,
+    e: ArrayView1<Float>,
+    observer: Vec3,
+    w: ArrayView1<Float>,
+    occluders: &[Occluder],
+    subdivisions: usize,
+) -> Array1<Float> {
+    // Thermal emission of all facets of a mesh received by an observer at each wavelength
+    // (W/m3). Facets facing away from the observer or hidden by the occluders do not contribute,
+    // see illumination::illuminated_fraction with the observer in place of the Sun.
+    //
+    // t: surface temperature of each facet (K)
+    // e: emissivity of each facet
+    // observer: position of the observer in the frame of the mesh (m)
+    // w: wavelengths (m)
+    // occluders: bodies hiding facets, including the mesh itself, see illumination::Occluder
+    // subdivisions: see illumination::sample_points
+    let visible = super::illumination::illumination(mesh, observer, occluders, subdivisions, 1);
+    let mut flux = Array1::zeros(w.len());
+    for (ii, facet) in mesh.facets.iter().enumerate() {
+        if visible[ii] <= 0.0 {
+            continue;
+        }
+        let v = observer - facet.pos;
+        let d = v.length();
+        let cose = crate::math::cosine_incidence(&(v / d), &facet.normal);
+        let sr = steradian(facet.area * visible[ii], d);
+        for (f, &w) in flux.iter_mut().zip(w) {
+            *f += irradiance(spectral_radiance(planck(t[ii], w), e[ii], cose, 1.0), sr);
+        }
+    }
+    flux
+}
+
+pub fn light_curve(
+    mesh: &Mesh,
+    t: ArrayView2<Float>,
+    e: ArrayView1<Float>,
+    observers: &[Vec3],
+    w: ArrayView1<Float>,
+    subdivisions: usize,
+) -> Result<Array2<Float>> {
+    // Spectral flux density received by an observer over a time series (time, wavelength) in
+    // W/m3, see spectral_flux_density. Facets are hidden by the mesh itself only.
+    //
+    // t: surface temperatures (time, facet) (K), e.g. a record of the simulation
+    // observers: position of the observer in the frame of the mesh at each time (m)
+    if observers.len() != t.nrows() {
+        return Err(anyhow!(
+            "Expected one observer per time step, got {} for {} time steps",
+            observers.len(),
+            t.nrows()
+        ));
+    }
+    let occluders = [Occluder::Mesh(mesh, crate::Mat4::IDENTITY)];
+    let mut flux = Array2::zeros((t.nrows(), w.len()));
+    for (it, &observer) in observers.iter().enumerate() {
+        flux.row_mut(it).assign(&spectral_flux_density(
+            mesh,
+            t.row(it),
+            e,
+            observer,
+            w,
+            &occluders,
+            subdivisions,
+        ));
+    }
+    Ok(flux)
+}
+
 // fn_f_sun = lambda x: planck(TEMP_SUN, x) * pi * RADIUS_SUN * RADIUS_SUN / (AU * AU)
 // S, _err = scipy.integrate.quad(fn_f_sun, 1e-10, 1e-2)
 // print(S)
 // = solar constant
 
 pub(crate) mod py {
-    use numpy::PyReadonlyArray1;
+    use numpy::ndarray::{Array, Dimension};
+    use numpy::{
+        PyArray1, PyArray2, PyArrayDyn, PyReadonlyArray1, PyReadonlyArray2, PyReadonlyArrayDyn,
+        ToPyArray,
+    };
+    use pyo3::exceptions::PyValueError;
     use pyo3::prelude::*;
 
     use super::Float;
+    use crate::{
+        Mat4, Vec3,
+        py::{
+            mesh::Mesh,
+            tpm::response::{BandPlanck, SpectralResponse},
+        },
+        tpm::illumination::Occluder,
+    };
 
     #[pyfunction]
     pub fn radiance(
@@ -80,4 +256,182 @@ pub(crate) mod py {
     ) -> PyResult<Float> {
         Ok(super::radiance(f, r.as_array(), w.as_array()))
     }
+
+    fn check_facets(mesh: &crate::mesh::Mesh, nt: usize, ne: usize) -> PyResult<()> {
+        // Temperatures and emissivities given for each facet of the mesh.
+        let n = mesh.facets.len();
+        if nt != n || ne != n {
+            return Err(PyValueError::new_err(format!(
+                "Expected temperatures and emissivities of the {} facets, got {} and {}",
+                n, nt, ne
+            )));
+        }
+        Ok(())
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, t, e, observer, w, subdivisions=1, jansky=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn spectral_flux_density<'py>(
+        py: Python<'py>,
+        mesh: Bound<'py, Mesh>,
+        t: PyReadonlyArray1<'py, Float>,
+        e: PyReadonlyArray1<'py, Float>,
+        observer: PyReadonlyArray1<'py, Float>,
+        w: PyReadonlyArray1<'py, Float>,
+        subdivisions: usize,
+        jansky: bool,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        // Facets are hidden by the mesh itself only.
+        //
+        // jansky: flux density per frequency (Jy) instead of per wavelength (W/m3)
+        let mesh = mesh.borrow().inner.clone();
+        let mesh = mesh.borrow();
+        check_facets(&mesh, t.as_array().len(), e.as_array().len())?;
+        let observer = Vec3::from_slice(observer.as_slice()?);
+        let w = w.as_array();
+        let mut flux = super::spectral_flux_density(
+            &mesh,
+            t.as_array(),
+            e.as_array(),
+            observer,
+            w,
+            &[Occluder::Mesh(&mesh, Mat4::IDENTITY)],
+            subdivisions,
+        );
+        if jansky {
+            flux.zip_mut_with(&w, |f, &w| *f = super::jansky(*f, w));
+        }
+        Ok(flux.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, t, e, observers, w, subdivisions=1, jansky=false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn light_curve<'py>(
+        py: Python<'py>,
+        mesh: Bound<'py, Mesh>,
+        t: PyReadonlyArray2<'py, Float>,
+        e: PyReadonlyArray1<'py, Float>,
+        observers: PyReadonlyArray2<'py, Float>,
+        w: PyReadonlyArray1<'py, Float>,
+        subdivisions: usize,
+        jansky: bool,
+    ) -> PyResult<Bound<'py, PyArray2<Float>>> {
+        // observers: position of the observer at each time (time, 3) (m)
+        // jansky: flux density per frequency (Jy) instead of per wavelength (W/m3)
+        let mesh = mesh.borrow().inner.clone();
+        let mesh = mesh.borrow();
+        let (t, observers) = (t.as_array(), observers.as_array());
+        check_facets(&mesh, t.ncols(), e.as_array().len())?;
+        if observers.ncols() != 3 {
+            return Err(PyValueError::new_err(format!(
+                "Expected observers of shape (time, 3), got {:?}",
+                observers.shape()
+            )));
+        }
+        let observers: Vec<_> = observers
+            .rows()
+            .into_iter()
+            .map(|r| Vec3::new(r[0], r[1], r[2]))
+            .collect();
+        let w = w.as_array();
+        let mut flux = super::light_curve(&mesh, t, e.as_array(), &observers, w, subdivisions)
+            .map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+        if jansky {
+            for mut row in flux.rows_mut() {
+                row.zip_mut_with(&w, |f, &w| *f = super::jansky(*f, w));
+            }
+        }
+        Ok(flux.to_pyarray(py))
+    }
+
+    fn broadcast<D: Dimension>(
+        x: Option<PyReadonlyArrayDyn<'_, Float>>,
+        dim: D,
+        default: Float,
+        name: &str,
+    ) -> PyResult<Array<Float, D>> {
+        // Array of shape dim from an optional array broadcastable to it.
+        let Some(x) = x else {
+            return Ok(Array::from_elem(dim, default));
+        };
+        let x = x.as_array();
+        x.broadcast(dim.clone())
+            .map(|x| x.to_owned())
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "{} of shape {:?} cannot be broadcast to {:?}",
+                    name,
+                    x.shape(),
+                    dim.slice()
+                ))
+            })
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (f, w, e=None, reflected=None))]
+    pub fn brightness_temperature_array<'py>(
+        py: Python<'py>,
+        f: PyReadonlyArrayDyn<'py, Float>,
+        w: Float,
+        e: Option<PyReadonlyArrayDyn<'py, Float>>,
+        reflected: Option<PyReadonlyArrayDyn<'py, Float>>,
+    ) -> PyResult<Bound<'py, PyArrayDyn<Float>>> {
+        // f: observed spectral radiance of an image or of facets (W/m3/sr)
+        // e: spectral emissivity broadcastable to f, 1 if None
+        // reflected: reflected sunlight broadcastable to f, 0 if None
+        let f = f.as_array();
+        let e = broadcast(e, f.raw_dim(), 1.0, "e")?;
+        let reflected = broadcast(reflected, f.raw_dim(), 0.0, "reflected")?;
+        let t = super::brightness_temperature_array(f, w, e.view(), reflected.view());
+        Ok(t.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (f, table, e=1.0, reflected=0.0))]
+    pub fn band_brightness_temperature(
+        f: Float,
+        table: PyRef<'_, BandPlanck>,
+        e: Float,
+        reflected: Float,
+    ) -> Float {
+        super::band_brightness_temperature(f, &table.inner.borrow(), e, reflected)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (f, table, e=None, reflected=None))]
+    pub fn band_brightness_temperature_array<'py>(
+        py: Python<'py>,
+        f: PyReadonlyArrayDyn<'py, Float>,
+        table: PyRef<'py, BandPlanck>,
+        e: Option<PyReadonlyArrayDyn<'py, Float>>,
+        reflected: Option<PyReadonlyArrayDyn<'py, Float>>,
+    ) -> PyResult<Bound<'py, PyArrayDyn<Float>>> {
+        // f: observed band radiance of an image or of facets (W/m3/sr)
+        // e: emissivity over the band broadcastable to f, 1 if None
+        // reflected: reflected sunlight broadcastable to f, 0 if None
+        let f = f.as_array();
+        let e = broadcast(e, f.raw_dim(), 1.0, "e")?;
+        let reflected = broadcast(reflected, f.raw_dim(), 0.0, "reflected")?;
+        let t = super::band_brightness_temperature_array(
+            f,
+            &table.inner.borrow(),
+            e.view(),
+            reflected.view(),
+        );
+        Ok(t.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    pub fn band_reflectance(
+        response: PyRef<'_, SpectralResponse>,
+        a: Float,
+        area: Float,
+        cose: Float,
+        d: Float,
+        r: Float,
+    ) -> Float {
+        super::band_reflectance(&response.inner.borrow(), a, area, cose, d, r)
+    }
 }
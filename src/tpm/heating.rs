@@ -0,0 +1,526 @@
+use std::path::Path;
+
+use anyhow::Result;
+use numpy::ndarray::{Array1, ArrayView1};
+
+use super::viewfactors::{ViewFactorMethod, ViewFactors};
+use crate::{Float, Mat4, Vec3, mesh::Mesh};
+
+pub fn view_factor_matrix(
+    mesh: &Mesh,
+    method: ViewFactorMethod,
+    occlusion: bool,
+    cutoff: Float,
+    threads: usize,
+) -> ViewFactors {
+    // View factors between all facets of a mesh, vf[i, j] is the view factor from facet i to
+    // facet j including the area of j, so the irradiance received by i is the sum over j of
+    // vf[i, j] times the exitance of j (W/m2). Pairs where one facet is behind the other are
+    // culled, close facets are subdivided (see mesh::view_factor_triangles).
+    //
+    // method: analytic or Monte-Carlo estimator, see ViewFactorMethod
+    // occlusion: pairs of facets hidden by another facet of the mesh are removed
+    // cutoff: pairs with both view factors not above are removed
+    // threads: threads computing the pairs, all available if 0
+    let n = mesh.facets.len();
+    let triangles = match method {
+        ViewFactorMethod::Analytic => mesh.triangles(),
+        ViewFactorMethod::MonteCarlo { .. } => vec![],
+    };
+    if occlusion || method != ViewFactorMethod::Analytic {
+        mesh.bvh();
+    }
+
+    // rows are interleaved between threads as the number of pairs decreases along rows
+    let triplets = pairs_threaded(n, threads, |rows| match method {
+        ViewFactorMethod::Analytic => {
+            pairs_analytic(mesh, &triangles, rows, |ii| ii + 1..n, occlusion, cutoff)
+        }
+        ViewFactorMethod::MonteCarlo { rays, seed } => {
+            pairs_monte_carlo(mesh, rows, occlusion, rays, seed)
+        }
+    });
+    let vf = ViewFactors::from_triplets(n, n, triplets);
+    match method {
+        ViewFactorMethod::Analytic => vf,
+        ViewFactorMethod::MonteCarlo { .. } => vf.pruned(cutoff),
+    }
+}
+
+fn pairs_threaded<F>(n: usize, threads: usize, pairs: F) -> Vec<(u32, u32, Float)>
+where
+    F: Fn(std::iter::StepBy<std::ops::Range<usize>>) -> Vec<(u32, u32, Float)> + Sync,
+{
+    // Triplets of the pairs of the n rows, rows interleaved between the threads.
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let pairs = &pairs;
+    crossbeam::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|first| scope.spawn(move |_| pairs((first..n).step_by(threads))))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+    .unwrap()
+}
+
+fn pairs_analytic(
+    mesh: &Mesh,
+    triangles: &[[Vec3; 3]],
+    rows: impl Iterator<Item = usize>,
+    columns: impl Fn(usize) -> std::ops::Range<usize>,
+    occlusion: bool,
+    cutoff: Float,
+) -> Vec<(u32, u32, Float)> {
+    // View factors of the pairs of a facet of rows with the facets of its columns, both
+    // directions.
+    let facets = &mesh.facets;
+    let mut triplets = vec![];
+    for ii in rows {
+        let fa = &facets[ii];
+        for jj in columns(ii) {
+            let fb = &facets[jj];
+            let v = fb.pos - fa.pos;
+            if fa.normal.dot(v) <= 0.0 || fb.normal.dot(v) >= 0.0 {
+                continue;
+            }
+            let f =
+                crate::mesh::view_factor_triangles(&triangles[ii], &triangles[jj], &Mat4::IDENTITY);
+            let (vij, vji) = (f * fb.area, f * fa.area);
+            if vij.max(vji) <= cutoff || (occlusion && is_occluded(mesh, ii, jj)) {
+                continue;
+            }
+            triplets.push((ii as u32, jj as u32, vij));
+            triplets.push((jj as u32, ii as u32, vji));
+        }
+    }
+    triplets
+}
+
+fn pairs_monte_carlo(
+    mesh: &Mesh,
+    rows: impl Iterator<Item = usize>,
+    occlusion: bool,
+    rays: usize,
+    seed: u64,
+) -> Vec<(u32, u32, Float)> {
+    // View factors estimated from the facets of rows, made reciprocal (area_i vf[i, j] equals
+    // area_j vf[j, i]) by averaging the estimates of both facets of each pair. Duplicates are
+    // summed by ViewFactors::from_triplets.
+    let facets = &mesh.facets;
+    let mut triplets = vec![];
+    for ii in rows {
+        for (jj, f) in view_factors_monte_carlo(mesh, ii, occlusion, rays, seed) {
+            let ratio = facets[ii].area / facets[jj].area;
+            triplets.push((ii as u32, jj as u32, 0.5 * f));
+            triplets.push((jj as u32, ii as u32, 0.5 * f * ratio));
+        }
+    }
+    triplets
+}
+
+pub fn view_factors_monte_carlo(
+    mesh: &Mesh,
+    facet: usize,
+    occlusion: bool,
+    rays: usize,
+    seed: u64,
+) -> Vec<(usize, Float)> {
+    // View factors from a facet to the other facets of the mesh estimated with rays from random
+    // points of the facet in cosine-weighted directions. Only facets hit on their front side are
+    // counted. Returns the facets seen and their view factors, sorted by facet.
+    //
+    // occlusion: only the first facet hit by a ray is counted, all of them otherwise
+    // rays: number of rays traced
+    // seed: seed of the random numbers, combined with the index of the facet
+    let f = &mesh.facets[facet];
+    let [a, b, c] = mesh.triangle(facet);
+    let (t1, t2) = f.normal.any_orthonormal_pair();
+    let offset = f.normal * 1e-3 * f.area.sqrt();
+    let mut rng = SplitMix64(seed ^ (facet as u64).wrapping_mul(0x9e3779b97f4a7c15));
+    let mut hits = vec![0usize; mesh.facets.len()];
+    for _ in 0..rays {
+        let (mut r1, mut r2) = (rng.next_float(), rng.next_float());
+        if r1 + r2 > 1.0 {
+            (r1, r2) = (1.0 - r1, 1.0 - r2);
+        }
+        let p = a + r1 * (b - a) + r2 * (c - a) + offset;
+        let (r, phi) = (
+            rng.next_float().sqrt(),
+            2.0 * crate::util::PI * rng.next_float(),
+        );
+        let u = r * phi.cos() * t1 + r * phi.sin() * t2 + (1.0 - r * r).max(0.0).sqrt() * f.normal;
+        let front = |jj: usize| jj != facet && mesh.facets[jj].normal.dot(u) < 0.0;
+        if occlusion {
+            if let Some(hit) = mesh.intersect_closest(&p, &u)
+                && front(hit.facet)
+            {
+                hits[hit.facet] += 1;
+            }
+        } else {
+            for hit in mesh.intersect_all(&p, &u) {
+                if front(hit.facet) {
+                    hits[hit.facet] += 1;
+                }
+            }
+        }
+    }
+    hits.into_iter()
+        .enumerate()
+        .filter(|&(_, n)| n > 0)
+        .map(|(jj, n)| (jj, n as Float / rays as Float))
+        .collect()
+}
+
+// SplitMix64 generator, small and good enough for sampling. Used instead of a random crate to
+// give the same numbers on all platforms.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1).
+    fn next_float(&mut self) -> Float {
+        (self.next_u64() >> 40) as Float / (1u64 << 24) as Float
+    }
+}
+
+pub fn view_factor_matrix_cached(
+    mesh: &Mesh,
+    method: ViewFactorMethod,
+    occlusion: bool,
+    cutoff: Float,
+    threads: usize,
+    dir: impl AsRef<Path>,
+) -> Result<ViewFactors> {
+    // View factor matrix read from a directory if it was already computed for the same mesh and
+    // parameters, computed and saved there otherwise. Files are named after their key, see
+    // viewfactors::cache_key. A file not matching the facets of the mesh is computed again.
+    let key = super::viewfactors::cache_key(mesh, method, occlusion, cutoff);
+    let path = dir.as_ref().join(format!("{:016x}.vf", key));
+    let n = mesh.facets.len();
+    if path.exists()
+        && let Ok(vf) = ViewFactors::load(&path, key)
+        && vf.nrows == n
+        && vf.ncols == n
+    {
+        return Ok(vf);
+    }
+    let vf = view_factor_matrix(mesh, method, occlusion, cutoff, threads);
+    std::fs::create_dir_all(dir.as_ref())?;
+    vf.save(&path, key)?;
+    Ok(vf)
+}
+
+pub fn view_factor_matrix_bodies(
+    mesh_a: &Mesh,
+    mesh_b: &Mesh,
+    trans_b2a: &Mat4,
+    occlusion: bool,
+    cutoff: Float,
+    threads: usize,
+) -> (ViewFactors, ViewFactors) {
+    // View factors from the facets of body A to the facets of body B and from B to A, same
+    // convention as view_factor_matrix. Each pair is computed once for both directions, from the
+    // meshes joined in the body-fixed frame of A.
+    //
+    // trans_b2a: transformation from the body-fixed frame of B to the one of A
+    // occlusion: pairs of facets hidden by another facet of either body are removed
+    let (na, nb) = (mesh_a.facets.len(), mesh_b.facets.len());
+    let mesh = joined(mesh_a, mesh_b, trans_b2a);
+    let triangles = mesh.triangles();
+    if occlusion {
+        mesh.bvh();
+    }
+    let triplets = pairs_threaded(na, threads, |rows| {
+        pairs_analytic(&mesh, &triangles, rows, |_| na..na + nb, occlusion, cutoff)
+    });
+    let (mut ab, mut ba) = (vec![], vec![]);
+    for (ii, jj, v) in triplets {
+        match ii < na as u32 {
+            true => ab.push((ii, jj - na as u32, v)),
+            false => ba.push((ii - na as u32, jj, v)),
+        }
+    }
+    (
+        ViewFactors::from_triplets(na, nb, ab),
+        ViewFactors::from_triplets(nb, na, ba),
+    )
+}
+
+fn joined(mesh_a: &Mesh, mesh_b: &Mesh, trans_b2a: &Mat4) -> Mesh {
+    // Facets of A followed by the facets of B moved to the frame of A, in one mesh.
+    let triangles = mesh_a.triangles().into_iter().chain(
+        mesh_b
+            .triangles()
+            .into_iter()
+            .map(|t| t.map(|p| trans_b2a.transform_point3(p))),
+    );
+    let facets_b = mesh_b.facets.iter().map(|f| crate::mesh::Facet {
+        pos: trans_b2a.transform_point3(f.pos),
+        normal: trans_b2a.transform_vector3(f.normal),
+        area: f.area,
+    });
+    let mut mesh = Mesh::new();
+    mesh.vertices = triangles
+        .flatten()
+        .map(|pos| crate::mesh::Vertex {
+            pos,
+            ..crate::mesh::Vertex::default()
+        })
+        .collect();
+    mesh.indices = (0..mesh.vertices.len() as u32).collect();
+    mesh.facets = mesh_a.facets.iter().copied().chain(facets_b).collect();
+    mesh
+}
+
+pub fn is_occluded(mesh: &Mesh, a: usize, b: usize) -> bool {
+    // Whether another facet of the mesh lies on the segment between the centres of facets a and b.
+    // The segment starts slightly above facet a to not hit a itself.
+    let (fa, fb) = (&mesh.facets[a], &mesh.facets[b]);
+    let p = fa.pos + fa.normal * 1e-3 * fa.area.sqrt();
+    let v = fb.pos - p;
+    let distance = v.length();
+    match mesh.intersect_closest(&p, &(v / distance)) {
+        Some(hit) => hit.facet != b && hit.facet != a && hit.distance < distance,
+        None => false,
+    }
+}
+
+pub fn scattered_irradiance(
+    vf: &ViewFactors,
+    irradiance: ArrayView1<'_, Float>,
+    albedo: ArrayView1<'_, Float>,
+    orders: usize,
+) -> Array1<Float> {
+    // Sunlight received by each facet after scattering by the other facets, Lambertian surfaces.
+    // Each order scatters the light received at the previous order, 1 for single scattering.
+    //
+    // vf: view factor matrix, see view_factor_matrix
+    // irradiance: sunlight received directly by each facet (W/m2)
+    // albedo: albedo of each facet
+    let mut received = irradiance.to_owned();
+    let mut total = Array1::zeros(irradiance.len());
+    for _ in 0..orders {
+        received = vf.dot((&received * &albedo).view());
+        total += &received;
+    }
+    total
+}
+
+pub fn thermal_irradiance(
+    vf: &ViewFactors,
+    t: ArrayView1<'_, Float>,
+    emissivity: ArrayView1<'_, Float>,
+) -> Array1<Float> {
+    // Thermal emission received by each facet from the other facets.
+    //
+    // t: surface temperature of each facet (K)
+    // emissivity: emissivity of each facet
+    let exitance = crate::util::STEFAN_BOLTZMANN * &emissivity * &t.mapv(|t| t.powi(4));
+    vf.dot(exitance.view())
+}
+
+pub fn self_heating(
+    vf: &ViewFactors,
+    irradiance: ArrayView1<'_, Float>,
+    t: ArrayView1<'_, Float>,
+    albedo: ArrayView1<'_, Float>,
+    emissivity: ArrayView1<'_, Float>,
+    orders: usize,
+) -> Array1<Float> {
+    // Flux absorbed by each facet from the scattered sunlight and thermal emission of the other
+    // facets, to add to the absorbed direct sunlight in the surface energy balance.
+    // The thermal emission is absorbed with the emissivity (Kirchhoff's law).
+    let scattered = scattered_irradiance(vf, irradiance, albedo, orders);
+    let thermal = thermal_irradiance(vf, t, emissivity);
+    (1.0 - &albedo) * &scattered + &emissivity * &thermal
+}
+
+pub fn mutual_heating(
+    vf: &ViewFactors,
+    irradiance: ArrayView1<'_, Float>,
+    t: ArrayView1<'_, Float>,
+    albedo: ArrayView1<'_, Float>,
+    emissivity: ArrayView1<'_, Float>,
+    albedo_receiver: ArrayView1<'_, Float>,
+    emissivity_receiver: ArrayView1<'_, Float>,
+) -> Array1<Float> {
+    // Flux absorbed by each facet of a body from the sunlight scattered once and the thermal
+    // emission of the facets of another body.
+    //
+    // vf: view factor matrix from the receiving body, see view_factor_matrix_bodies
+    // irradiance, t, albedo, emissivity: sunlight received, surface temperature, albedo and
+    // emissivity of each facet of the emitting body
+    // albedo_receiver, emissivity_receiver: albedo and emissivity of the receiving facets
+    let scattered = vf.dot((&irradiance * &albedo).view());
+    let thermal = thermal_irradiance(vf, t, emissivity);
+    (1.0 - &albedo_receiver) * &scattered + &emissivity_receiver * &thermal
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArray2, ToPyArray};
+    use pyo3::prelude::*;
+
+    use crate::{
+        Float,
+        py::{mesh::Mesh, tpm::viewfactors::ViewFactors},
+        tpm::viewfactors::ViewFactorMethod,
+    };
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, occlusion=true, cutoff=0.0, threads=0, cache=None, rays=None, seed=0))]
+    pub fn view_factor_matrix(
+        mesh: Bound<'_, Mesh>,
+        occlusion: bool,
+        cutoff: Float,
+        threads: usize,
+        cache: Option<&str>,
+        rays: Option<usize>,
+        seed: u64,
+    ) -> PyResult<ViewFactors> {
+        // cache: directory of the saved matrices, see view_factor_matrix_cached
+        // rays: rays traced from each facet for the Monte-Carlo estimator, analytic if None
+        // seed: seed of the Monte-Carlo estimator
+        let mesh = mesh.borrow().inner.clone();
+        let mesh = mesh.borrow();
+        let method = ViewFactorMethod::from_rays(rays, seed);
+        let vf = match cache {
+            Some(dir) => {
+                super::view_factor_matrix_cached(&mesh, method, occlusion, cutoff, threads, dir)
+                    .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))?
+            }
+            None => super::view_factor_matrix(&mesh, method, occlusion, cutoff, threads),
+        };
+        Ok(ViewFactors::from_inner(vf))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, facet, rays, seed=0, occlusion=true))]
+    pub fn view_factors_monte_carlo(
+        mesh: Bound<'_, Mesh>,
+        facet: usize,
+        rays: usize,
+        seed: u64,
+        occlusion: bool,
+    ) -> Vec<(usize, Float)> {
+        super::view_factors_monte_carlo(&mesh.borrow().inner.borrow(), facet, occlusion, rays, seed)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh_a, mesh_b, trans_b2a, occlusion=true, cutoff=0.0, threads=0))]
+    pub fn view_factor_matrix_bodies<'py>(
+        mesh_a: Bound<'py, Mesh>,
+        mesh_b: Bound<'py, Mesh>,
+        trans_b2a: PyReadonlyArray2<'py, Float>,
+        occlusion: bool,
+        cutoff: Float,
+        threads: usize,
+    ) -> PyResult<(ViewFactors, ViewFactors)> {
+        // output view factors from A to B and from B to A
+        let (ab, ba) = super::view_factor_matrix_bodies(
+            &mesh_a.borrow().inner.borrow(),
+            &mesh_b.borrow().inner.borrow(),
+            &crate::py::util::mat4(&trans_b2a)?,
+            occlusion,
+            cutoff,
+            threads,
+        );
+        Ok((ViewFactors::from_inner(ab), ViewFactors::from_inner(ba)))
+    }
+
+    #[pyfunction]
+    pub fn mesh_hash(mesh: Bound<'_, Mesh>) -> u64 {
+        crate::tpm::viewfactors::mesh_hash(&mesh.borrow().inner.borrow())
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (vf, irradiance, albedo, orders=1))]
+    pub fn scattered_irradiance<'py>(
+        py: Python<'py>,
+        vf: Bound<'py, ViewFactors>,
+        irradiance: PyReadonlyArray1<'py, Float>,
+        albedo: PyReadonlyArray1<'py, Float>,
+        orders: usize,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::scattered_irradiance(
+            &vf.borrow().inner.borrow(),
+            irradiance.as_array(),
+            albedo.as_array(),
+            orders,
+        )
+        .to_pyarray(py)
+    }
+
+    #[pyfunction]
+    pub fn thermal_irradiance<'py>(
+        py: Python<'py>,
+        vf: Bound<'py, ViewFactors>,
+        t: PyReadonlyArray1<'py, Float>,
+        emissivity: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::thermal_irradiance(
+            &vf.borrow().inner.borrow(),
+            t.as_array(),
+            emissivity.as_array(),
+        )
+        .to_pyarray(py)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (vf, irradiance, t, albedo, emissivity, orders=1))]
+    pub fn self_heating<'py>(
+        py: Python<'py>,
+        vf: Bound<'py, ViewFactors>,
+        irradiance: PyReadonlyArray1<'py, Float>,
+        t: PyReadonlyArray1<'py, Float>,
+        albedo: PyReadonlyArray1<'py, Float>,
+        emissivity: PyReadonlyArray1<'py, Float>,
+        orders: usize,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::self_heating(
+            &vf.borrow().inner.borrow(),
+            irradiance.as_array(),
+            t.as_array(),
+            albedo.as_array(),
+            emissivity.as_array(),
+            orders,
+        )
+        .to_pyarray(py)
+    }
+
+    #[pyfunction]
+    #[allow(clippy::too_many_arguments)]
+    pub fn mutual_heating<'py>(
+        py: Python<'py>,
+        vf: Bound<'py, ViewFactors>,
+        irradiance: PyReadonlyArray1<'py, Float>,
+        t: PyReadonlyArray1<'py, Float>,
+        albedo: PyReadonlyArray1<'py, Float>,
+        emissivity: PyReadonlyArray1<'py, Float>,
+        albedo_receiver: PyReadonlyArray1<'py, Float>,
+        emissivity_receiver: PyReadonlyArray1<'py, Float>,
+    ) -> Bound<'py, PyArray1<Float>> {
+        super::mutual_heating(
+            &vf.borrow().inner.borrow(),
+            irradiance.as_array(),
+            t.as_array(),
+            albedo.as_array(),
+            emissivity.as_array(),
+            albedo_receiver.as_array(),
+            emissivity_receiver.as_array(),
+        )
+        .to_pyarray(py)
+    }
+}
@@ -0,0 +1,253 @@
+use numpy::ndarray::Array1;
+
+use crate::{Float, Mat4, Vec3, mesh::Mesh};
+
+pub fn sample_points(mesh: &Mesh, facet: usize, subdivisions: usize) -> Vec<Vec3> {
+    // Points of a facet where the illumination is sampled, the centroids of the subdivisions^2
+    // triangles of equal area obtained by dividing each edge in subdivisions segments.
+    // A single subdivision is the centre of the facet.
+    let [a, b, c] = mesh.get_facet_positions(facet);
+    let (ab, ac) = (b - a, c - a);
+    let k = subdivisions.max(1);
+    let point = |u: Float, v: Float| a + ab * (u / k as Float) + ac * (v / k as Float);
+    let mut points = Vec::with_capacity(k * k);
+    for ii in 0..k {
+        for jj in 0..k - ii {
+            points.push(point(ii as Float + 1.0 / 3.0, jj as Float + 1.0 / 3.0));
+            if ii + jj + 1 < k {
+                points.push(point(ii as Float + 2.0 / 3.0, jj as Float + 2.0 / 3.0));
+            }
+        }
+    }
+    points
+}
+
+// Body casting shadows on a mesh.
+#[derive(Clone, Copy)]
+pub enum Occluder<'a> {
+    // Mesh and the transformation from the frame of the illuminated mesh to its frame.
+    Mesh(&'a Mesh, Mat4),
+
+    // Ellipsoid of radii (m) along the axes of its frame centred at its origin, and the
+    // transformation from the frame of the illuminated mesh to this frame, see
+    // entity::Body::radii.
+    Ellipsoid(Vec3, Mat4),
+}
+
+impl<'a> Occluder<'a> {
+    // Mesh from the transformation of its frame to the frame of the illuminated mesh, inverted
+    // once here rather than for each ray.
+    pub fn mesh(mesh: &'a Mesh, trans: Mat4) -> Self {
+        Self::Mesh(mesh, trans.inverse())
+    }
+
+    // Ellipsoid from the transformation of its frame to the frame of the illuminated mesh.
+    pub fn ellipsoid(radii: Vec3, trans: Mat4) -> Self {
+        Self::Ellipsoid(radii, trans.inverse())
+    }
+
+    // Whether a ray hits the occluder closer than a distance.
+    //
+    // p: origin of the ray
+    // u: direction of the ray, normalized
+    pub fn intersects(&self, p: Vec3, u: Vec3, max_distance: Float) -> bool {
+        match self {
+            Self::Mesh(mesh, inv) => {
+                let (p, u) = (inv.transform_point3(p), inv.transform_vector3(u));
+                mesh.intersect_any(&p, &u, max_distance).is_some()
+            }
+            Self::Ellipsoid(radii, inv) => {
+                let (p, u) = (inv.transform_point3(p), inv.transform_vector3(u));
+                intersect_ellipsoid(p, u, *radii).is_some_and(|t| t < max_distance)
+            }
+        }
+    }
+}
+
+pub fn intersect_ellipsoid(p: Vec3, u: Vec3, radii: Vec3) -> Option<Float> {
+    // Distance along a ray to the first intersection with an ellipsoid centred at the origin,
+    // None if missed or behind the origin of the ray. If the origin is inside, the distance to
+    // the exit.
+    //
+    // u: direction of the ray, normalized
+    let (p, v) = (p / radii, u / radii);
+    let (a, b, c) = (v.length_squared(), p.dot(v), p.length_squared() - 1.0);
+    let delta = b * b - a * c;
+    if delta < 0.0 {
+        return None;
+    }
+    let sq = delta.sqrt();
+    [(-b - sq) / a, (-b + sq) / a]
+        .into_iter()
+        .find(|&t| t > 0.0)
+}
+
+pub fn sun_disk_points(p: Vec3, sun: Vec3, radius: Float, samples: usize) -> Vec<Vec3> {
+    // Points of the disk of the Sun seen from a point, of equal area along a sunflower spiral.
+    // A single sample is the centre of the Sun.
+    //
+    // p: position of the observer (m)
+    // sun: position of the centre of the Sun (m)
+    // radius: radius of the Sun (m)
+    if samples <= 1 {
+        return vec![sun];
+    }
+    let (t1, t2) = (sun - p).normalize().any_orthonormal_pair();
+    let golden = crate::util::PI * (3.0 - (5.0 as Float).sqrt());
+    (0..samples)
+        .map(|k| {
+            let r = radius * ((k as Float + 0.5) / samples as Float).sqrt();
+            let angle = golden * k as Float;
+            sun + r * (angle.cos() * t1 + angle.sin() * t2)
+        })
+        .collect()
+}
+
+pub fn is_shadowed(p: Vec3, sun: Vec3, occluders: &[Occluder]) -> bool {
+    // Whether the ray from a point towards the Sun hits a body of the scene.
+    //
+    // p: position of the point (m)
+    // sun: position of the Sun (m)
+    // occluders: bodies of the scene in the frame of p and sun, only between them
+    let v = sun - p;
+    let d = v.length();
+    occluders
+        .iter()
+        .any(|occluder| occluder.intersects(p, v / d, d))
+}
+
+pub fn visible_sun_fraction(p: Vec3, sun: Vec3, occluders: &[Occluder], samples: usize) -> Float {
+    // Fraction of the disk of the Sun visible from a point, the Sun is a uniform disk of radius
+    // RADIUS_SUN sampled with rays, see sun_disk_points. Smooth in the penumbra for enough
+    // samples, 0 or 1 for a point Sun (samples 0 or 1).
+    let points = sun_disk_points(p, sun, crate::util::RADIUS_SUN, samples);
+    let visible = points
+        .iter()
+        .filter(|&&s| !is_shadowed(p, s, occluders))
+        .count();
+    visible as Float / points.len() as Float
+}
+
+pub fn illuminated_fraction(
+    mesh: &Mesh,
+    facet: usize,
+    sun: Vec3,
+    occluders: &[Occluder],
+    subdivisions: usize,
+    sun_samples: usize,
+) -> Float {
+    // Fraction of a facet illuminated by the Sun, 0 if the facet faces away from the Sun.
+    // Rays are cast towards the Sun from the sample points of the facet, slightly above the
+    // surface to not hit the facet itself.
+    //
+    // sun: position of the Sun in the frame of the mesh (m)
+    // occluders: bodies casting shadows in the frame of the mesh, including the mesh itself with
+    // the identity for self-shadowing
+    // subdivisions: see sample_points, 1 for a ray from the centre only
+    // sun_samples: points of the disk of the Sun, see visible_sun_fraction
+    let f = &mesh.facets[facet];
+    if f.normal.dot(sun - f.pos) <= 0.0 {
+        return 0.0;
+    }
+    let offset = f.normal * 1e-3 * f.area.sqrt();
+    let points = sample_points(mesh, facet, subdivisions);
+    let lit: Float = points
+        .iter()
+        .map(|&p| visible_sun_fraction(p + offset, sun, occluders, sun_samples))
+        .sum();
+    lit / points.len() as Float
+}
+
+pub fn illumination(
+    mesh: &Mesh,
+    sun: Vec3,
+    occluders: &[Occluder],
+    subdivisions: usize,
+    sun_samples: usize,
+) -> Array1<Float> {
+    // Illuminated fraction of all facets of a mesh, see illuminated_fraction.
+    // The absorbed sunlight of a facet is radiation_sun with the cosine of incidence multiplied
+    // by its fraction.
+    Array1::from_shape_fn(mesh.facets.len(), |ii| {
+        illuminated_fraction(mesh, ii, sun, occluders, subdivisions, sun_samples)
+    })
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArray2, ToPyArray};
+    use pyo3::prelude::*;
+
+    use super::Occluder;
+    use crate::{Float, Mat4, Vec3, py::mesh::Mesh};
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, sun, subdivisions=1, others=vec![], trans_others=vec![], sun_samples=1, ellipsoids=vec![], trans_ellipsoids=vec![]))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn illumination<'py>(
+        py: Python<'py>,
+        mesh: Bound<'py, Mesh>,
+        sun: PyReadonlyArray1<'py, Float>,
+        subdivisions: usize,
+        others: Vec<Bound<'py, Mesh>>,
+        trans_others: Vec<PyReadonlyArray2<'py, Float>>,
+        sun_samples: usize,
+        ellipsoids: Vec<[Float; 3]>,
+        trans_ellipsoids: Vec<PyReadonlyArray2<'py, Float>>,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        // others: meshes of other bodies casting shadows
+        // trans_others: transformation from the frame of each other body to the frame of mesh
+        // sun_samples: points of the disk of the Sun, 1 for a point Sun
+        // ellipsoids: radii of bodies casting shadows modelled as ellipsoids (m)
+        // trans_ellipsoids: transformation from the frame of each ellipsoid to the frame of mesh
+        if others.len() != trans_others.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Expected one transformation per other body",
+            ));
+        }
+        if ellipsoids.len() != trans_ellipsoids.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Expected one transformation per ellipsoid",
+            ));
+        }
+        let mesh = mesh.borrow().inner.clone();
+        let others: Vec<_> = others.iter().map(|m| m.borrow().inner.clone()).collect();
+        let mesh = mesh.borrow();
+        let others: Vec<_> = others.iter().map(|m| m.borrow()).collect();
+        let mut occluders = vec![Occluder::Mesh(&mesh, Mat4::IDENTITY)];
+        for (other, trans) in others.iter().zip(&trans_others) {
+            let trans = crate::py::util::mat4(trans)?;
+            occluders.push(Occluder::mesh(other, trans));
+        }
+        for (radii, trans) in ellipsoids.iter().zip(&trans_ellipsoids) {
+            let trans = crate::py::util::mat4(trans)?;
+            occluders.push(Occluder::ellipsoid(Vec3::from_array(*radii), trans));
+        }
+        let sun = crate::py::util::vec3(&sun)?;
+        Ok(super::illumination(&mesh, sun, &occluders, subdivisions, sun_samples).to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (p, sun, samples=64, ellipsoids=vec![], trans_ellipsoids=vec![]))]
+    pub fn visible_sun_fraction<'py>(
+        p: PyReadonlyArray1<'py, Float>,
+        sun: PyReadonlyArray1<'py, Float>,
+        samples: usize,
+        ellipsoids: Vec<[Float; 3]>,
+        trans_ellipsoids: Vec<PyReadonlyArray2<'py, Float>>,
+    ) -> PyResult<Float> {
+        // Visible fraction of the disk of the Sun from a point, with ellipsoids only.
+        if ellipsoids.len() != trans_ellipsoids.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Expected one transformation per ellipsoid",
+            ));
+        }
+        let mut occluders = vec![];
+        for (radii, trans) in ellipsoids.iter().zip(&trans_ellipsoids) {
+            let trans = crate::py::util::mat4(trans)?;
+            occluders.push(Occluder::ellipsoid(Vec3::from_array(*radii), trans));
+        }
+        let p = crate::py::util::vec3(&p)?;
+        let sun = crate::py::util::vec3(&sun)?;
+        Ok(super::visible_sun_fraction(p, sun, &occluders, samples))
+    }
+}
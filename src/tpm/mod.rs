@@ -2,4 +2,12 @@ pub mod core;
 pub mod properties;
 pub mod emit;
 pub mod routine;
-pub mod column;
\ No newline at end of file
+pub mod column;
+pub mod analytical;
+pub mod heating;
+pub mod illumination;
+pub mod viewfactors;
+pub mod planetshine;
+pub mod recoil;
+pub mod response;
+pub mod simple;
\ No newline at end of file
@@ -0,0 +1,174 @@
+use numpy::ndarray::Array1;
+
+use crate::{Float, Mat4, Vec3, mesh::Facet};
+
+// Surface temperature of a host planet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlanetTemperature {
+    // Same temperature everywhere (K).
+    Uniform(Float),
+
+    // Instantaneous radiative equilibrium with the absorbed sunlight, not below the temperature
+    // of the night side (K).
+    Equilibrium { night: Float },
+}
+
+impl PlanetTemperature {
+    // Temperature of a surface element.
+    //
+    // absorbed: sunlight absorbed by the element (W/m2)
+    // emissivity: emissivity of the planet
+    pub fn at(&self, absorbed: Float, emissivity: Float) -> Float {
+        match *self {
+            Self::Uniform(t) => t,
+            Self::Equilibrium { night } => {
+                let t = (absorbed / (emissivity * crate::util::STEFAN_BOLTZMANN)).powf(0.25);
+                t.max(night)
+            }
+        }
+    }
+}
+
+pub fn ellipsoid_elements(radii: Vec3, resolution: usize) -> Vec<Facet> {
+    // Surface elements of an ellipsoid centred at the origin, on a grid of planetocentric
+    // latitudes and longitudes with resolution bands of latitude and twice as many of longitude.
+    // The area of each element is the one of the ellipsoid between its bounds, approximated at
+    // its centre.
+    //
+    // radii: radii along the axes (m)
+    let n = resolution.max(1);
+    let (dlat, dlon) = (crate::util::PI / n as Float, crate::util::PI / n as Float);
+    let mut elements = Vec::with_capacity(2 * n * n);
+    for ii in 0..n {
+        let lat = -crate::util::PI / 2.0 + (ii as Float + 0.5) * dlat;
+        for jj in 0..2 * n {
+            let lon = (jj as Float + 0.5) * dlon;
+            let (sl, cl) = lat.sin_cos();
+            let (so, co) = lon.sin_cos();
+            let pos = radii * Vec3::new(cl * co, cl * so, sl);
+            // partial derivatives of the position along latitude and longitude
+            let dpos_lat = radii * Vec3::new(-sl * co, -sl * so, cl);
+            let dpos_lon = radii * Vec3::new(-cl * so, cl * co, 0.0);
+            elements.push(Facet {
+                pos,
+                normal: (pos / (radii * radii)).normalize(),
+                area: dpos_lat.cross(dpos_lon).length() * dlat * dlon,
+            });
+        }
+    }
+    elements
+}
+
+pub fn planetshine(
+    facets: &[Facet],
+    elements: &[Facet],
+    trans: &Mat4,
+    sun: Vec3,
+    albedo: Float,
+    emissivity: Float,
+    temperature: PlanetTemperature,
+) -> (Array1<Float>, Array1<Float>) {
+    // Sunlight reflected and thermal emission of a host planet received by facets, before
+    // absorption (W/m2). Each surface element of the planet is a Lambertian reflector and
+    // emitter seen from the facets where both face each other, which accounts for the phase of
+    // the planet and the part of it below the horizon of the facets. The planet is convex so it
+    // does not hide its own elements, topography of the receiving body is not considered.
+    //
+    // facets: receiving facets
+    // elements: surface elements of the planet in its frame, see ellipsoid_elements
+    // trans: transformation from the frame of the planet to the frame of the facets
+    // sun: position of the Sun in the frame of the facets (m)
+    // albedo, emissivity: of the surface of the planet
+    let elements: Vec<_> = elements
+        .iter()
+        .map(|e| Facet {
+            pos: trans.transform_point3(e.pos),
+            normal: trans.transform_vector3(e.normal).normalize(),
+            area: e.area,
+        })
+        .collect();
+
+    // radiance of each element (W/m2/sr)
+    let radiance: Vec<_> = elements
+        .iter()
+        .map(|e| {
+            let v_sun = sun - e.pos;
+            let d_sun = v_sun.length();
+            let cosi = crate::math::cosine_incidence(&(v_sun / d_sun), &e.normal);
+            let irradiance = crate::tpm::core::radiation_sun(d_sun / crate::util::AU, cosi, 0.0);
+            let t = temperature.at((1.0 - albedo) * irradiance, emissivity);
+            let thermal = emissivity * crate::util::STEFAN_BOLTZMANN * t.powi(4);
+            (
+                albedo * irradiance / crate::util::PI,
+                thermal / crate::util::PI,
+            )
+        })
+        .collect();
+
+    let mut reflected = Array1::zeros(facets.len());
+    let mut thermal = Array1::zeros(facets.len());
+    for (ii, f) in facets.iter().enumerate() {
+        for (e, &(lr, lt)) in elements.iter().zip(&radiance) {
+            let v = e.pos - f.pos;
+            let (cos_f, cos_e) = (f.normal.dot(v), -e.normal.dot(v));
+            if cos_f <= 0.0 || cos_e <= 0.0 {
+                continue;
+            }
+            // cosines are not normalized, hence the distance to the fourth power
+            let w = cos_f * cos_e * e.area / v.length_squared().powi(2);
+            reflected[ii] += lr * w;
+            thermal[ii] += lt * w;
+        }
+    }
+    (reflected, thermal)
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArray2, ToPyArray};
+    use pyo3::prelude::*;
+
+    use super::PlanetTemperature;
+    use crate::{Float, Vec3, py::mesh::Mesh};
+
+    // Reflected and thermal flux of each facet (W/m2).
+    type Fluxes<'py> = (Bound<'py, PyArray1<Float>>, Bound<'py, PyArray1<Float>>);
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, radii, trans, sun, albedo, emissivity, temperature=None, night=0.0, resolution=32))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn planetshine<'py>(
+        py: Python<'py>,
+        mesh: Bound<'py, Mesh>,
+        radii: [Float; 3],
+        trans: PyReadonlyArray2<'py, Float>,
+        sun: PyReadonlyArray1<'py, Float>,
+        albedo: Float,
+        emissivity: Float,
+        temperature: Option<Float>,
+        night: Float,
+        resolution: usize,
+    ) -> PyResult<Fluxes<'py>> {
+        // radii: radii of the planet (m)
+        // trans: transformation from the frame of the planet to the frame of mesh
+        // temperature: uniform temperature of the planet (K), radiative equilibrium if None
+        // night: lowest temperature of the planet at radiative equilibrium (K)
+        // resolution: bands of latitude of the surface elements, see ellipsoid_elements
+        let temperature = match temperature {
+            Some(t) => PlanetTemperature::Uniform(t),
+            None => PlanetTemperature::Equilibrium { night },
+        };
+        let trans = crate::py::util::mat4(&trans)?;
+        let sun = crate::py::util::vec3(&sun)?;
+        let elements = super::ellipsoid_elements(Vec3::from_array(radii), resolution);
+        let (reflected, thermal) = super::planetshine(
+            &mesh.borrow().inner.borrow().facets,
+            &elements,
+            &trans,
+            sun,
+            albedo,
+            emissivity,
+            temperature,
+        );
+        Ok((reflected.to_pyarray(py), thermal.to_pyarray(py)))
+    }
+}
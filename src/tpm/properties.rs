@@ -10,6 +10,8 @@ pub const DIDYMOS: Properties = Properties {
     thermal_inertia: 320.0,
     conductivity: 0.0,
     diffusivity: 0.0,
+    conductivity_model: ConductivityModel::Constant,
+    heat_capacity_model: HeatCapacityModel::Constant,
 };
 
 pub const DIMORPHOS: Properties = Properties {
@@ -20,6 +22,8 @@ pub const DIMORPHOS: Properties = Properties {
     thermal_inertia: 320.0,
     conductivity: 0.0,
     diffusivity: 0.0,
+    conductivity_model: ConductivityModel::Constant,
+    heat_capacity_model: HeatCapacityModel::Constant,
 };
 
 pub const MOON: Properties = Properties {
@@ -30,6 +34,8 @@ pub const MOON: Properties = Properties {
     thermal_inertia: 55.0,
     conductivity: 0.0,
     diffusivity: 0.0,
+    conductivity_model: ConductivityModel::Constant,
+    heat_capacity_model: HeatCapacityModel::Constant,
 };
 
 // Wargnier2025: conductivity = 0.0683 or 0.0837)
@@ -42,6 +48,8 @@ pub const PHOBOS: Properties = Properties {
     thermal_inertia: 70.0,
     conductivity: 0.0,
     diffusivity: 0.0,
+    conductivity_model: ConductivityModel::Constant,
+    heat_capacity_model: HeatCapacityModel::Constant,
 };
 
 // Wargnier2025: thermal inertia = 20-85)
@@ -54,6 +62,8 @@ pub const DEIMOS: Properties = Properties {
     thermal_inertia: 20.0,
     conductivity: 0.0,
     diffusivity: 0.0,
+    conductivity_model: ConductivityModel::Constant,
+    heat_capacity_model: HeatCapacityModel::Constant,
 };
 
 #[pyfunction]
@@ -101,6 +111,87 @@ pub fn skin_depth_2pi(d: Float, p: Float) -> Float {
     (4.0 * crate::util::PI * d * p).sqrt()
 }
 
+#[pyfunction]
+#[pyo3(signature = (chi=2.7))]
+pub fn r350(chi: Float) -> Float {
+    // Radiative conductivity parameter, Mitchell and de Pater (1994), heat1d.
+    //
+    // chi: ratio of radiative to contact conductivity at 350 K
+    chi / (350.0 * 350.0 * 350.0)
+}
+
+#[pyfunction]
+pub fn conductivity_radiative(k: Float, t: Float, r: Float) -> Float {
+    // Temperature-dependent conductivity, Mitchell and de Pater (1994), Vasavada et al. (2012).
+    //
+    // k: contact conductivity (...)
+    // t: temperature (K)
+    // r: radiative conductivity parameter (K-3), see r350
+    k * (1.0 + r * t.powi(3))
+}
+
+pub fn heat_capacity_polynomial(c: &[Float], t: Float) -> Float {
+    // Temperature-dependent heat capacity, Ledlow et al. (1992), Hemingway et al. (1981).
+    // Valid for T > ~10 K, non-physical below 1.3 K with lunar coefficients.
+    //
+    // c: polynomial coefficients, highest degree first (...)
+    // t: temperature (K)
+    c.iter().fold(0.0, |acc, c| acc * t + c)
+}
+
+// Lunar regolith heat capacity coefficients, Hayne et al. (2017), heat1d.
+pub const HEAT_CAPACITY_MOON: [Float; 5] = [8.9093e-9, -1.234e-5, 2.3616e-3, 2.7431, -3.6125];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ConductivityModel {
+    // Use Properties.conductivity.
+    #[default]
+    Constant,
+
+    // Properties.conductivity is the contact conductivity and the value is the radiative
+    // parameter, see conductivity_radiative.
+    Radiative(Float),
+}
+
+impl ConductivityModel {
+    // Conductivity at temperature from the conductivity of the model, contact conductivity for
+    // radiative model.
+    pub fn conductivity(&self, k: Float, t: Float) -> Float {
+        match self {
+            Self::Constant => k,
+            Self::Radiative(r) => conductivity_radiative(k, t, *r),
+        }
+    }
+
+    // Derivative of conductivity with temperature.
+    pub fn derivative(&self, k: Float, t: Float) -> Float {
+        match self {
+            Self::Constant => 0.0,
+            Self::Radiative(r) => 3.0 * k * r * t.powi(2),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum HeatCapacityModel {
+    // Use Properties.heat_capacity.
+    #[default]
+    Constant,
+
+    // Polynomial coefficients, see heat_capacity_polynomial.
+    Polynomial(Vec<Float>),
+}
+
+impl HeatCapacityModel {
+    // Heat capacity at temperature, c is used by constant model only.
+    pub fn heat_capacity(&self, c: Float, t: Float) -> Float {
+        match self {
+            Self::Constant => c,
+            Self::Polynomial(coefs) => heat_capacity_polynomial(coefs, t),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct Properties {
     pub albedo: Float,
@@ -110,6 +201,8 @@ pub struct Properties {
     pub thermal_inertia: Float,
     pub conductivity: Float,
     pub diffusivity: Float,
+    pub conductivity_model: ConductivityModel,
+    pub heat_capacity_model: HeatCapacityModel,
 }
 
 impl Properties {
@@ -130,9 +223,38 @@ impl Properties {
             thermal_inertia,
             conductivity,
             diffusivity,
+            conductivity_model: ConductivityModel::Constant,
+            heat_capacity_model: HeatCapacityModel::Constant,
         }
     }
 
+    pub fn is_temperature_dependent(&self) -> bool {
+        self.conductivity_model != ConductivityModel::Constant
+            || self.heat_capacity_model != HeatCapacityModel::Constant
+    }
+
+    pub fn conductivity_at(&self, t: Float) -> Float {
+        self.conductivity_model.conductivity(self.conductivity, t)
+    }
+
+    // Derivative of conductivity with temperature.
+    pub fn conductivity_derivative_at(&self, t: Float) -> Float {
+        self.conductivity_model.derivative(self.conductivity, t)
+    }
+
+    pub fn heat_capacity_at(&self, t: Float) -> Float {
+        self.heat_capacity_model
+            .heat_capacity(self.heat_capacity, t)
+    }
+
+    pub fn diffusivity_at(&self, t: Float) -> Float {
+        diffusivity(
+            self.conductivity_at(t),
+            self.density,
+            self.heat_capacity_at(t),
+        )
+    }
+
     pub fn compute_thermal_inertia(&mut self) {
         self.thermal_inertia = thermal_inertia(self.conductivity, self.density, self.heat_capacity);
     }
@@ -155,14 +277,27 @@ impl std::fmt::Debug for Properties {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Properties(albedo={}, emissivity={}, density={}, heat_capacity={}, thermal_inertia={}, conductivity={}, diffusivity={})",
+            "Properties(albedo={}, emissivity={}, density={}, heat_capacity={}, thermal_inertia={}, conductivity={}, diffusivity={}, conductivity_model={:?}, heat_capacity_model={:?})",
             self.albedo,
             self.emissivity,
             self.density,
             self.heat_capacity,
             self.thermal_inertia,
             self.conductivity,
-            self.diffusivity
+            self.diffusivity,
+            self.conductivity_model,
+            self.heat_capacity_model,
         )
     }
 }
+
+pub(crate) mod py {
+    use pyo3::prelude::*;
+
+    use crate::Float;
+
+    #[pyfunction]
+    pub fn heat_capacity_polynomial(c: Vec<Float>, t: Float) -> Float {
+        super::heat_capacity_polynomial(&c, t)
+    }
+}
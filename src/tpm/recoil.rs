@@ -0,0 +1,255 @@
+use anyhow::{Result, anyhow};
+use numpy::ndarray::{ArrayView1, ArrayView2};
+
+use super::viewfactors::ViewFactors;
+use crate::{Float, Mat4, Vec3, mesh::Facet};
+
+// Force and torque of the photons leaving the surface of a body (Yarkovsky and YORP effects).
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Recoil {
+    // (N)
+    pub force: Vec3,
+
+    // about the origin of the body-fixed frame, the centre of mass (N m)
+    pub torque: Vec3,
+}
+
+impl std::ops::Add for Recoil {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            force: self.force + other.force,
+            torque: self.torque + other.torque,
+        }
+    }
+}
+
+impl std::fmt::Debug for Recoil {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Recoil(force={}, torque={})", self.force, self.torque)
+    }
+}
+
+pub fn recoil_facet(facet: &Facet, exitance: Float, seen: Vec3) -> Recoil {
+    // Recoil of the photons leaving a Lambertian facet. The mean direction of the photons is
+    // 2/3 of the normal, the photons absorbed by other facets of the body give back their
+    // momentum to the body and do not contribute.
+    //
+    // exitance: power emitted and reflected per unit area (W/m2)
+    // seen: sum over the other facets of the view factor times the unit vector towards them,
+    // 0 without self-heating
+    let force =
+        -exitance * facet.area / crate::util::SPEED_LIGHT * (2.0 / 3.0 * facet.normal - seen);
+    Recoil {
+        force,
+        torque: facet.pos.cross(force),
+    }
+}
+
+pub fn recoil(
+    facets: &[Facet],
+    t: ArrayView1<'_, Float>,
+    irradiance: ArrayView1<'_, Float>,
+    albedo: ArrayView1<'_, Float>,
+    emissivity: ArrayView1<'_, Float>,
+    vf: Option<&ViewFactors>,
+) -> Recoil {
+    // Recoil of the thermal emission and reflected sunlight of all facets of a body, in the
+    // body-fixed frame. The absorption of the incident sunlight (radiation pressure) is not
+    // included.
+    //
+    // t: surface temperature of each facet (K)
+    // irradiance: sunlight received by each facet, including scattering by other facets (W/m2)
+    // albedo, emissivity: of each facet
+    // vf: view factors between facets, see tpm::heating::view_factor_matrix
+    facets
+        .iter()
+        .enumerate()
+        .map(|(ii, facet)| {
+            let exitance = emissivity[ii] * crate::util::STEFAN_BOLTZMANN * t[ii].powi(4)
+                + albedo[ii] * irradiance[ii];
+            let seen = vf.map_or(Vec3::ZERO, |vf| {
+                let (indices, values) = vf.row(ii);
+                indices
+                    .iter()
+                    .zip(values)
+                    .map(|(&jj, &v)| v * (facets[jj as usize].pos - facet.pos).normalize())
+                    .sum()
+            });
+            recoil_facet(facet, exitance, seen)
+        })
+        .fold(Recoil::default(), |a, b| a + b)
+}
+
+pub fn recoil_mean(
+    facets: &[Facet],
+    t: ArrayView2<'_, Float>,
+    irradiance: ArrayView2<'_, Float>,
+    albedo: ArrayView1<'_, Float>,
+    emissivity: ArrayView1<'_, Float>,
+    vf: Option<&ViewFactors>,
+    states: &[Mat4],
+) -> Result<Recoil> {
+    // Recoil averaged over samples evenly spaced in time, e.g. over a rotation or an orbit.
+    // The force is averaged in the frame of the model matrices to not cancel as the body spins,
+    // the torque in the body-fixed frame.
+    //
+    // t, irradiance: (time, facet), see recoil
+    // states: model matrix of the body at each sample (body-fixed frame to world frame), the
+    // force stays in the body-fixed frame if empty
+    let n = t.nrows();
+    if !states.is_empty() && states.len() != n {
+        return Err(anyhow!(
+            "Expected no model matrix or one per sample, got {} for {} samples",
+            states.len(),
+            n
+        ));
+    }
+    let sum = (0..n)
+        .map(|it| {
+            let r = recoil(
+                facets,
+                t.row(it),
+                irradiance.row(it),
+                albedo,
+                emissivity,
+                vf,
+            );
+            match states.get(it) {
+                Some(state) => Recoil {
+                    force: state.transform_vector3(r.force),
+                    torque: r.torque,
+                },
+                None => r,
+            }
+        })
+        .fold(Recoil::default(), |a, b| a + b);
+    Ok(Recoil {
+        force: sum.force / n.max(1) as Float,
+        torque: sum.torque / n.max(1) as Float,
+    })
+}
+
+pub fn yarkovsky_drift(
+    force: Vec3,
+    velocity: Vec3,
+    mass: Float,
+    semi_major_axis: Float,
+    gm: Float,
+) -> Float {
+    // Drift of the semi-major axis of a circular orbit from the mean transverse recoil force
+    // (m/s), da/dt = 2 F_t / (m n) with the mean motion n.
+    //
+    // force: mean force in the frame of the orbit (N), see recoil_mean
+    // velocity: direction of the orbital velocity
+    // mass: of the body (kg)
+    // semi_major_axis: (m)
+    // gm: gravitational parameter of the central body (m3/s2)
+    let n = (gm / semi_major_axis.powi(3)).sqrt();
+    2.0 * force.dot(velocity.normalize()) / (mass * n)
+}
+
+pub fn yorp_acceleration(torque: Vec3, spin_axis: Vec3, moment_of_inertia: Float) -> Float {
+    // Change of the spin rate from the mean recoil torque (rad/s2).
+    //
+    // torque: mean torque in the body-fixed frame (N m), see recoil_mean
+    // spin_axis: in the body-fixed frame
+    // moment_of_inertia: about the spin axis (kg m2)
+    torque.dot(spin_axis.normalize()) / moment_of_inertia
+}
+
+pub(crate) mod py {
+    use numpy::{PyReadonlyArray1, PyReadonlyArray2};
+    use pyo3::prelude::*;
+
+    use crate::{
+        Float, Vec3,
+        py::{mesh::Mesh, tpm::viewfactors::ViewFactors},
+    };
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, t, irradiance, albedo, emissivity, vf=None))]
+    pub fn recoil<'py>(
+        mesh: Bound<'py, Mesh>,
+        t: PyReadonlyArray1<'py, Float>,
+        irradiance: PyReadonlyArray1<'py, Float>,
+        albedo: PyReadonlyArray1<'py, Float>,
+        emissivity: PyReadonlyArray1<'py, Float>,
+        vf: Option<Bound<'py, ViewFactors>>,
+    ) -> ([Float; 3], [Float; 3]) {
+        // Force (N) and torque (N m) in the body-fixed frame.
+        let vf = vf.map(|vf| vf.borrow().inner.clone());
+        let vf = vf.as_ref().map(|vf| vf.borrow());
+        let r = super::recoil(
+            &mesh.borrow().inner.borrow().facets,
+            t.as_array(),
+            irradiance.as_array(),
+            albedo.as_array(),
+            emissivity.as_array(),
+            vf.as_deref(),
+        );
+        (r.force.to_array(), r.torque.to_array())
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (mesh, t, irradiance, albedo, emissivity, vf=None, states=vec![]))]
+    pub fn recoil_mean<'py>(
+        mesh: Bound<'py, Mesh>,
+        t: PyReadonlyArray2<'py, Float>,
+        irradiance: PyReadonlyArray2<'py, Float>,
+        albedo: PyReadonlyArray1<'py, Float>,
+        emissivity: PyReadonlyArray1<'py, Float>,
+        vf: Option<Bound<'py, ViewFactors>>,
+        states: Vec<PyReadonlyArray2<'py, Float>>,
+    ) -> PyResult<([Float; 3], [Float; 3])> {
+        // states: model matrix of the body at each sample
+        let states = states
+            .iter()
+            .map(crate::py::util::mat4)
+            .collect::<PyResult<Vec<_>>>()?;
+        let vf = vf.map(|vf| vf.borrow().inner.clone());
+        let vf = vf.as_ref().map(|vf| vf.borrow());
+        let r = super::recoil_mean(
+            &mesh.borrow().inner.borrow().facets,
+            t.as_array(),
+            irradiance.as_array(),
+            albedo.as_array(),
+            emissivity.as_array(),
+            vf.as_deref(),
+            &states,
+        )
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:#}", e)))?;
+        Ok((r.force.to_array(), r.torque.to_array()))
+    }
+
+    #[pyfunction]
+    pub fn yarkovsky_drift(
+        force: [Float; 3],
+        velocity: [Float; 3],
+        mass: Float,
+        semi_major_axis: Float,
+        gm: Float,
+    ) -> Float {
+        super::yarkovsky_drift(
+            Vec3::from_array(force),
+            Vec3::from_array(velocity),
+            mass,
+            semi_major_axis,
+            gm,
+        )
+    }
+
+    #[pyfunction]
+    pub fn yorp_acceleration(
+        torque: [Float; 3],
+        spin_axis: [Float; 3],
+        moment_of_inertia: Float,
+    ) -> Float {
+        super::yorp_acceleration(
+            Vec3::from_array(torque),
+            Vec3::from_array(spin_axis),
+            moment_of_inertia,
+        )
+    }
+}
@@ -0,0 +1,290 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use numpy::ndarray::{Array1, ArrayView1};
+
+use crate::Float;
+
+// Spectral response of a filter: transmission of the optics and filter times the sensitivity of
+// the detector, tabulated at increasing wavelengths. It is 0 outside of the table and linear
+// between its points.
+#[derive(Clone, PartialEq)]
+pub struct SpectralResponse {
+    pub name: String,
+
+    // wavelengths (m)
+    pub w: Array1<Float>,
+
+    pub r: Array1<Float>,
+}
+
+impl SpectralResponse {
+    pub fn new(name: &str, w: Array1<Float>, r: Array1<Float>) -> Result<Self> {
+        if w.len() != r.len() {
+            return Err(anyhow!(
+                "Response {} has {} wavelengths and {} values",
+                name,
+                w.len(),
+                r.len()
+            ));
+        }
+        if w.len() < 2 {
+            return Err(anyhow!("Response {} needs at least 2 points", name));
+        }
+        if w.windows(2).into_iter().any(|x| x[1] <= x[0]) {
+            return Err(anyhow!("Response {} wavelengths are not increasing", name));
+        }
+        // band averages are divided by the integral of the response
+        let width = crate::math::trapez(r.view(), w.view());
+        if width.is_nan() || width <= 0.0 {
+            return Err(anyhow!(
+                "Response {} has an integral of {} over wavelength, expected positive",
+                name,
+                width
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            w,
+            r,
+        })
+    }
+
+    // Response of 1 between two wavelengths (m).
+    pub fn boxcar(name: &str, w_min: Float, w_max: Float) -> Result<Self> {
+        Self::new(
+            name,
+            Array1::from_vec(vec![w_min, w_max]),
+            Array1::from_vec(vec![1.0, 1.0]),
+        )
+    }
+
+    // Read a table of two columns, wavelength and response, separated by commas, semicolons or
+    // whitespaces. Empty lines, lines starting with # and a first line that is not numeric
+    // (header) are skipped.
+    //
+    // unit: wavelength of the file in meters, e.g. 1e-6 for micrometers
+    pub fn load_csv(path: impl AsRef<Path>, name: &str, unit: Float) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read response {}", path.display()))?;
+        let (mut w, mut r) = (vec![], vec![]);
+        for (ii, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values: Vec<_> = line
+                .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<Float>())
+                .collect();
+            match values.as_slice() {
+                [Ok(x), Ok(y), ..] => {
+                    w.push(x * unit);
+                    r.push(*y);
+                }
+                _ if w.is_empty() && ii == 0 => continue,
+                _ => {
+                    return Err(anyhow!(
+                        "Cannot read response {} at line {}: {}",
+                        path.display(),
+                        ii + 1,
+                        line
+                    ));
+                }
+            }
+        }
+        Self::new(name, Array1::from_vec(w), Array1::from_vec(r))
+    }
+
+    // Response at a wavelength (m).
+    pub fn at(&self, w: Float) -> Float {
+        let n = self.w.len();
+        if w < self.w[0] || w > self.w[n - 1] {
+            return 0.0;
+        }
+        let ii = self.w.as_slice().unwrap().partition_point(|&x| x <= w);
+        let ii = ii.clamp(1, n - 1);
+        let f = (w - self.w[ii - 1]) / (self.w[ii] - self.w[ii - 1]);
+        self.r[ii - 1] + f * (self.r[ii] - self.r[ii - 1])
+    }
+
+    // Response on other wavelengths (m), increasing.
+    pub fn resample(&self, w: ArrayView1<Float>) -> Result<Self> {
+        Self::new(&self.name, w.to_owned(), w.mapv(|w| self.at(w)))
+    }
+
+    // Response on n wavelengths evenly spaced over the table.
+    pub fn resample_uniform(&self, n: usize) -> Result<Self> {
+        let (w_min, w_max) = (self.w[0], self.w[self.w.len() - 1]);
+        self.resample(Array1::linspace(w_min, w_max, n).view())
+    }
+
+    // Response scaled to a maximum of 1.
+    pub fn normalized(&self) -> Self {
+        let max = self.r.fold(0.0 as Float, |a, &b| a.max(b));
+        let mut response = self.clone();
+        if max > 0.0 {
+            response.r /= max;
+        }
+        response
+    }
+
+    // Integral of the response over wavelength (m).
+    pub fn width(&self) -> Float {
+        crate::math::trapez(self.r.view(), self.w.view())
+    }
+
+    // Mean wavelength weighted by the response (m).
+    pub fn effective_wavelength(&self) -> Float {
+        let wr = &self.w * &self.r;
+        crate::math::trapez(wr.view(), self.w.view()) / self.width()
+    }
+
+    // Spectral radiance of a black body averaged over the response (W/m3/sr), see
+    // emit::planck.
+    //
+    // t: temperature (K)
+    pub fn band_radiance(&self, t: Float) -> Float {
+        let y = Array1::from_shape_fn(self.w.len(), |ii| {
+            super::emit::planck(t, self.w[ii]) * self.r[ii]
+        });
+        crate::math::trapez(y.view(), self.w.view()) / self.width()
+    }
+}
+
+impl std::fmt::Debug for SpectralResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SpectralResponse(name={}, points={}, w=[{}, {}])",
+            self.name,
+            self.w.len(),
+            self.w[0],
+            self.w[self.w.len() - 1],
+        )
+    }
+}
+
+// Lookup tables of the band radiance of a response, see SpectralResponse::band_radiance, and of
+// its inverse, the brightness temperature. Both are linear interpolations in constant time, the
+// logarithm of the radiance on temperatures evenly spaced and the temperature on logarithms of
+// the radiance evenly spaced. Values outside of the tables are clamped.
+#[derive(Clone, PartialEq)]
+pub struct BandPlanck {
+    // range of the temperatures (K)
+    pub t_min: Float,
+    pub t_max: Float,
+
+    // logarithm of the band radiance at the temperatures
+    pub log_radiance: Vec<Float>,
+
+    // range of the logarithms of the band radiance
+    pub log_min: Float,
+    pub log_max: Float,
+
+    // temperatures at the logarithms of the band radiance (K)
+    pub temperature: Vec<Float>,
+}
+
+impl BandPlanck {
+    // t_min: lowest temperature, above 0 (K)
+    // t_max: highest temperature (K)
+    // n: number of points of the tables
+    pub fn new(response: &SpectralResponse, t_min: Float, t_max: Float, n: usize) -> Result<Self> {
+        if t_min <= 0.0 || t_min >= t_max {
+            return Err(anyhow!(
+                "Temperatures of the band {} from {} to {} K, expected 0 < t_min < t_max",
+                response.name,
+                t_min,
+                t_max
+            ));
+        }
+        let n = n.max(2);
+        let ts = Array1::linspace(t_min, t_max, n);
+        // the radiance of low temperatures can be 0 at short wavelengths
+        let log_radiance: Vec<_> = ts
+            .iter()
+            .map(|&t| response.band_radiance(t).max(Float::MIN_POSITIVE).ln())
+            .collect();
+        let (log_min, log_max) = (log_radiance[0], log_radiance[n - 1]);
+        if log_min >= log_max {
+            return Err(anyhow!(
+                "Band radiance of {} does not increase from {} to {} K",
+                response.name,
+                t_min,
+                t_max
+            ));
+        }
+        let logs = Array1::linspace(log_min, log_max, n);
+        let temperature = logs
+            .iter()
+            .map(|&l| {
+                // the radiance increases with the temperature
+                let ii = log_radiance.partition_point(|&x| x <= l).clamp(1, n - 1);
+                let (l0, l1) = (log_radiance[ii - 1], log_radiance[ii]);
+                let f = if l1 > l0 { (l - l0) / (l1 - l0) } else { 0.0 };
+                let t = ts[ii - 1] + f * (ts[ii] - ts[ii - 1]);
+                refine_temperature(response, l.exp(), t).clamp(t_min, t_max)
+            })
+            .collect();
+        Ok(Self {
+            t_min,
+            t_max,
+            log_radiance,
+            log_min,
+            log_max,
+            temperature,
+        })
+    }
+
+    // Band radiance at a temperature (W/m3/sr).
+    pub fn radiance(&self, t: Float) -> Float {
+        interpolate(&self.log_radiance, self.t_min, self.t_max, t).exp()
+    }
+
+    // Brightness temperature of a band radiance (K).
+    pub fn temperature(&self, radiance: Float) -> Float {
+        if radiance <= 0.0 {
+            return self.t_min;
+        }
+        interpolate(&self.temperature, self.log_min, self.log_max, radiance.ln())
+    }
+}
+
+impl std::fmt::Debug for BandPlanck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "BandPlanck(t_min={}, t_max={}, n={})",
+            self.t_min,
+            self.t_max,
+            self.log_radiance.len(),
+        )
+    }
+}
+
+// Linear interpolation of values evenly spaced between x_min and x_max, clamped outside.
+fn interpolate(values: &[Float], x_min: Float, x_max: Float, x: Float) -> Float {
+    let n = values.len();
+    let s = ((x - x_min) / (x_max - x_min) * (n - 1) as Float).clamp(0.0, (n - 1) as Float);
+    let ii = (s as usize).min(n - 2);
+    let f = s - ii as Float;
+    values[ii] + f * (values[ii + 1] - values[ii])
+}
+
+// Newton iterations on the temperature of a band radiance from a first guess.
+fn refine_temperature(response: &SpectralResponse, radiance: Float, t: Float) -> Float {
+    let mut t = t;
+    for _ in 0..4 {
+        let dt = 1e-3 * t;
+        let l = response.band_radiance(t);
+        let dl = (response.band_radiance(t + dt) - l) / dt;
+        if dl <= 0.0 {
+            break;
+        }
+        t -= (l - radiance) / dl;
+    }
+    t
+}
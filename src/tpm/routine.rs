@@ -1,32 +1,551 @@
-use numpy::ndarray::{Array1, ArrayView1, s};
+use numpy::ndarray::{Array1, ArrayView1, ArrayViewMut1, Zip, s};
 
+use super::{
+    core::{Boundaries, SurfaceTemperatureError},
+    properties::Properties,
+};
 use crate::Float;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Scheme {
+    // Forward finite difference, needs dt below stability_maxdt.
+    #[default]
+    Explicit,
+
+    // Theta scheme solved with a tridiagonal system: 0.5 Crank-Nicolson, 1 backward Euler.
+    Implicit(Float),
+}
+
+impl Scheme {
+    pub const CRANK_NICOLSON: Self = Self::Implicit(0.5);
+    pub const BACKWARD_EULER: Self = Self::Implicit(1.0);
+
+    pub fn from_theta(theta: Option<Float>) -> Self {
+        theta.map_or(Self::Explicit, Self::Implicit)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BottomBoundary {
+    // No flux through the bottom of the column.
+    #[default]
+    Adiabatic,
+
+    // Internal heat flux entering the column from below (W/m2), e.g. geothermal flux of the Moon.
+    Flux(Float),
+
+    // Fixed temperature at the bottom of the column (K).
+    Temperature(Float),
+}
+
+impl BottomBoundary {
+    // Temperature of the last node as slope * node above + offset, returned as (slope, offset).
+    // Implicit schemes fold it into the last row of the tridiagonal system.
+    //
+    // k: conductivity at the bottom (...)
+    // dx: depth step above the last node (m)
+    pub fn linear(&self, k: Float, dx: Float) -> (Float, Float) {
+        match *self {
+            Self::Adiabatic => (1.0, 0.0),
+            Self::Flux(q) => (1.0, q * dx / k),
+            Self::Temperature(x) => (0.0, x),
+        }
+    }
+
+    // Set the temperature of the last node of the column from the node above, see linear.
+    // Explicit schemes only impose the condition here, implicit schemes already solved the
+    // nodes inside with it and this recovers the last node.
+    pub fn apply(&self, mut t: ArrayViewMut1<'_, Float>, k: Float, dx: Float) {
+        let n = t.len();
+        let (slope, offset) = self.linear(k, dx);
+        t[n - 1] = slope * t[n - 2] + offset;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Fallback {
+    // Return the error of the solver.
+    #[default]
+    Raise,
+
+    // Use the last estimate of the solver clamped between min and max temperatures (K).
+    Clamp(Float, Float),
+
+    // Use the temperature at surface of the previous step.
+    Reuse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceSolver {
+    // convergence on surface temperature (K)
+    pub tolerance: Float,
+
+    pub max_iteration: usize,
+
+    // policy when the solver does not converge
+    pub fallback: Fallback,
+}
+
+impl Default for SurfaceSolver {
+    fn default() -> Self {
+        Self {
+            // tighter than NEWTON_METHOD_THRESHOLD of the standalone newton_method
+            tolerance: 1e-3,
+            max_iteration: crate::util::NEWTON_METHOD_MAX_ITERATION,
+            fallback: Fallback::Raise,
+        }
+    }
+}
+
+impl SurfaceSolver {
+    // Surface temperature with the fallback policy applied on failure, see
+    // tpm::core::surface_temperature.
+    pub fn solve<F>(
+        &self,
+        t: [Float; 3],
+        f: Float,
+        se: Float,
+        k: F,
+        g: [Float; 3],
+    ) -> Result<Float, SurfaceTemperatureError>
+    where
+        F: Fn(Float) -> (Float, Float),
+    {
+        let result =
+            super::core::surface_temperature(t, f, se, k, g, self.tolerance, self.max_iteration);
+        match (result, self.fallback) {
+            (Ok(x), _) => Ok(x),
+            (Err(e), Fallback::Raise) => Err(e),
+            (Err(e), Fallback::Clamp(min, max)) => Ok(e.estimate.clamp(min, max)),
+            (Err(_), Fallback::Reuse) => Ok(t[0]),
+        }
+    }
+}
+
+// Numerical methods of the update of a column.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColumnSolver {
+    pub scheme: Scheme,
+    pub bottom: BottomBoundary,
+    pub surface: SurfaceSolver,
+}
+
+// Finite difference coefficients of the depth grid of a column.
+#[derive(Clone, Copy, Debug)]
+pub enum Stencil<'a> {
+    // Constant depth step, see tpm::core::conduction_1d.
+    Uniform {
+        // dt / dx^2 of each node inside (s/m2)
+        dtpdx2: ArrayView1<'a, Float>,
+
+        // twice the depth step (m)
+        twodx: Float,
+    },
+
+    // Any depth grid, see tpm::column::depth_stencil.
+    Nonuniform {
+        dtpa: ArrayView1<'a, Float>,
+        dtpb: ArrayView1<'a, Float>,
+        g: [Float; 3],
+
+        // depth step above the last node (m)
+        dxb: Float,
+    },
+}
+
+impl<'a> Stencil<'a> {
+    // dt * a and dt * b of each node inside, see tpm::core::depth_steps_nonuniform.
+    pub fn coefficients(&self) -> (ArrayView1<'a, Float>, ArrayView1<'a, Float>) {
+        match *self {
+            Self::Uniform { dtpdx2, .. } => (dtpdx2, dtpdx2),
+            Self::Nonuniform { dtpa, dtpb, .. } => (dtpa, dtpb),
+        }
+    }
+
+    // Coefficients of the temperature gradient at surface, see tpm::core::surface_gradient.
+    pub fn gradient(&self) -> [Float; 3] {
+        match *self {
+            Self::Uniform { twodx, .. } => [-3.0 / twodx, 4.0 / twodx, -1.0 / twodx],
+            Self::Nonuniform { g, .. } => g,
+        }
+    }
+
+    // Depth step above the last node (m), see BottomBoundary.
+    pub fn dxb(&self) -> Float {
+        match *self {
+            Self::Uniform { twodx, .. } => twodx * 0.5,
+            Self::Nonuniform { dxb, .. } => dxb,
+        }
+    }
+}
+
 pub fn update_thermal_state(
     t: ArrayView1<'_, Float>,
     f: Float,
     d: ArrayView1<'_, Float>,
-    dtpdx2: ArrayView1<'_, Float>,
+    stencil: Stencil<'_>,
     se: Float,
     k: Float,
-    twodx: Float,
-) -> Array1<Float> {
+    solver: ColumnSolver,
+) -> Result<Array1<Float>, SurfaceTemperatureError> {
+    let ColumnSolver {
+        scheme,
+        bottom,
+        surface,
+    } = solver;
+    let n = t.len();
+    let mut new_t = t.to_owned();
+    new_t[0] = surface.solve([t[0], t[1], t[2]], f, se, |_| (k, 0.0), stencil.gradient())?;
+    let new_t_in = match (scheme, stencil) {
+        (Scheme::Explicit, Stencil::Uniform { dtpdx2, .. }) => {
+            super::core::conduction_1d(new_t.view(), d, dtpdx2)
+        }
+        (Scheme::Explicit, Stencil::Nonuniform { dtpa, dtpb, .. }) => {
+            super::core::conduction_1d_nonuniform(new_t.view(), d, dtpa, dtpb)
+        }
+        (Scheme::Implicit(theta), _) => {
+            let (dtpa, dtpb) = stencil.coefficients();
+            let linear = super::core::surface_linear(
+                [new_t[0], t[1], t[2]],
+                se,
+                (k, 0.0),
+                stencil.gradient(),
+            );
+            let t_in = super::core::conduction_1d_implicit_nonuniform(
+                t,
+                d,
+                dtpa,
+                dtpb,
+                theta,
+                Boundaries {
+                    surface: linear,
+                    bottom: bottom.linear(k, stencil.dxb()),
+                },
+            );
+            new_t[0] = linear[0] + linear[1] * t_in[0] + linear[2] * t_in[1];
+            t_in
+        }
+    };
+    new_t.slice_mut(s![1..n - 1]).assign(&new_t_in);
+    bottom.apply(new_t.view_mut(), k, stencil.dxb());
+    Ok(new_t)
+}
+
+pub fn update_thermal_state_properties(
+    t: ArrayView1<'_, Float>,
+    f: Float,
+    prop: &Properties,
+    stencil: Stencil<'_>,
+    solver: ColumnSolver,
+) -> Result<Array1<Float>, SurfaceTemperatureError> {
+    // Same as update_thermal_state with conductivity and heat capacity from the models of the
+    // thermal properties, evaluated at the temperatures of the previous step.
+    let n = t.len();
+    update_thermal_state_layered(
+        t,
+        f,
+        Layers {
+            p: Array1::from_elem(n, prop.density).view(),
+            c: Array1::from_elem(n, prop.heat_capacity).view(),
+            k: Array1::from_elem(n, prop.conductivity).view(),
+        },
+        prop,
+        stencil,
+        solver,
+    )
+}
+
+// Density, heat capacity and conductivity of each node of a column, before the temperature
+// dependence of the models of the thermal properties.
+#[derive(Clone, Copy, Debug)]
+pub struct Layers<'a> {
+    pub p: ArrayView1<'a, Float>,
+    pub c: ArrayView1<'a, Float>,
+    pub k: ArrayView1<'a, Float>,
+}
+
+pub fn update_thermal_state_layered(
+    t: ArrayView1<'_, Float>,
+    f: Float,
+    layers: Layers<'_>,
+    prop: &Properties,
+    stencil: Stencil<'_>,
+    solver: ColumnSolver,
+) -> Result<Array1<Float>, SurfaceTemperatureError> {
+    // Same as update_thermal_state_properties with density, heat capacity and conductivity
+    // varying with depth, see tpm::column::Column::new_layered. The models of the surface
+    // properties apply to all nodes.
+    //
+    let Layers { p, c, k } = layers;
+    let ColumnSolver {
+        scheme,
+        bottom,
+        surface,
+    } = solver;
     let n = t.len();
+    let se = crate::util::STEFAN_BOLTZMANN * prop.emissivity;
+    let kmodel = &prop.conductivity_model;
+    let cmodel = &prop.heat_capacity_model;
     let mut new_t = t.to_owned();
-    new_t[0] = super::core::newton_method(t[0], f, se, k, t[1], t[2], twodx).unwrap();
-    let new_t_in = super::core::conduction_1d(new_t.view(), d, dtpdx2);
-    new_t.slice_mut(s![1..-1]).assign(&new_t_in);
-    new_t[n - 1] = new_t[n - 2];
-    new_t
+    new_t[0] = surface.solve(
+        [t[0], t[1], t[2]],
+        f,
+        se,
+        |x| (kmodel.conductivity(k[0], x), kmodel.derivative(k[0], x)),
+        stencil.gradient(),
+    )?;
+    let (dtpa, dtpb) = stencil.coefficients();
+    let kt = Zip::from(&k)
+        .and(&new_t)
+        .map_collect(|&k, &x| kmodel.conductivity(k, x));
+    let pc = Zip::from(&p)
+        .and(&c)
+        .and(&t)
+        .map_collect(|&p, &c, &x| p * cmodel.heat_capacity(c, x));
+    let new_t_in = match scheme {
+        Scheme::Explicit => {
+            super::core::conduction_1d_variable(new_t.view(), kt.view(), pc.view(), dtpa, dtpb)
+        }
+        Scheme::Implicit(theta) => {
+            let x = new_t[0];
+            let linear = super::core::surface_linear(
+                [x, t[1], t[2]],
+                se,
+                (kt[0], kmodel.derivative(k[0], x)),
+                stencil.gradient(),
+            );
+            let t_in = super::core::conduction_1d_implicit_variable(
+                t,
+                kt.view(),
+                pc.view(),
+                dtpa,
+                dtpb,
+                theta,
+                Boundaries {
+                    surface: linear,
+                    bottom: bottom.linear(kt[n - 1], stencil.dxb()),
+                },
+            );
+            new_t[0] = linear[0] + linear[1] * t_in[0] + linear[2] * t_in[1];
+            t_in
+        }
+    };
+    new_t.slice_mut(s![1..n - 1]).assign(&new_t_in);
+    bottom.apply(new_t.view_mut(), kt[n - 1], stencil.dxb());
+    Ok(new_t)
+}
+
+// Properties of the nodes of a column for update_thermal_state_inplace.
+#[derive(Clone, Copy, Debug)]
+pub enum Nodes<'a> {
+    // Diffusivity of each node, conductivity of the surface properties, see
+    // update_thermal_state.
+    Diffusivity(ArrayView1<'a, Float>),
+
+    // Density, heat capacity and conductivity of each node, with the models of the surface
+    // properties, see update_thermal_state_layered.
+    Layered(Layers<'a>),
+}
+
+// Buffers of update_thermal_state_inplace reused between columns.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    k: Vec<Float>,
+    pc: Vec<Float>,
+    a: Vec<Float>,
+    b: Vec<Float>,
+    c: Vec<Float>,
+    d: Vec<Float>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn resize(&mut self, n: usize) {
+        for v in [
+            &mut self.k,
+            &mut self.pc,
+            &mut self.a,
+            &mut self.b,
+            &mut self.c,
+            &mut self.d,
+        ] {
+            v.resize(n, 0.0);
+        }
+    }
+}
+
+pub fn update_thermal_state_inplace(
+    mut t: ArrayViewMut1<'_, Float>,
+    f: Float,
+    nodes: Nodes<'_>,
+    prop: &Properties,
+    stencil: Stencil<'_>,
+    solver: ColumnSolver,
+    work: &mut Workspace,
+) -> Result<(), SurfaceTemperatureError> {
+    // Same as update_thermal_state and update_thermal_state_layered without allocation,
+    // temperatures are updated in place.
+    let ColumnSolver {
+        scheme,
+        bottom,
+        surface,
+    } = solver;
+    let n = t.len();
+    let (dtpa, dtpb) = stencil.coefficients();
+    let (g, dxb) = (stencil.gradient(), stencil.dxb());
+    let se = crate::util::STEFAN_BOLTZMANN * prop.emissivity;
+    let kmodel = &prop.conductivity_model;
+    let cmodel = &prop.heat_capacity_model;
+
+    let t3 = [t[0], t[1], t[2]];
+    let k_surface = |x: Float| match nodes {
+        Nodes::Diffusivity(_) => (prop.conductivity, 0.0),
+        Nodes::Layered(Layers { k, .. }) => {
+            (kmodel.conductivity(k[0], x), kmodel.derivative(k[0], x))
+        }
+    };
+    t[0] = surface.solve(t3, f, se, k_surface, g)?;
+
+    work.resize(n);
+    let k_bottom = match nodes {
+        Nodes::Diffusivity(d) => {
+            for ii in 0..n {
+                work.k[ii] = d[ii];
+                work.pc[ii] = 1.0;
+            }
+            prop.conductivity
+        }
+        Nodes::Layered(Layers { p, c, k }) => {
+            for ii in 0..n {
+                work.k[ii] = kmodel.conductivity(k[ii], t[ii]);
+                work.pc[ii] = p[ii] * cmodel.heat_capacity(c[ii], t[ii]);
+            }
+            work.k[n - 1]
+        }
+    };
+
+    // coefficients of neighbours of each node inside, with the diffusivity of the node as
+    // conduction_1d_nonuniform, or the conductivity at interfaces as conduction_1d_variable
+    let interfaces = matches!(nodes, Nodes::Layered(_));
+    let harmonic = |k1: Float, k2: Float| 2.0 * k1 * k2 / (k1 + k2);
+    let coefficients = |work: &Workspace, ii: usize| {
+        let (ka, kb) = if interfaces {
+            (
+                harmonic(work.k[ii - 1], work.k[ii]),
+                harmonic(work.k[ii], work.k[ii + 1]),
+            )
+        } else {
+            (work.k[ii], work.k[ii])
+        };
+        (
+            dtpa[ii - 1] * ka / work.pc[ii],
+            dtpb[ii - 1] * kb / work.pc[ii],
+        )
+    };
+
+    match scheme {
+        Scheme::Explicit => {
+            let mut above = t[0];
+            for ii in 1..n - 1 {
+                let (ra, rb) = coefficients(work, ii);
+                let ti = t[ii];
+                t[ii] = ti + ra * (above - ti) + rb * (t[ii + 1] - ti);
+                above = ti;
+            }
+        }
+        Scheme::Implicit(theta) => {
+            // surface of the new step from the nodes below at the new step, the previous
+            // surface temperature is used for the explicit part
+            let linear = super::core::surface_linear([t[0], t3[1], t3[2]], se, k_surface(t[0]), g);
+            let m = n - 2;
+            let mut above = t3[0];
+            for jj in 0..m {
+                let ii = jj + 1;
+                let (ra, rb) = coefficients(work, ii);
+                work.a[jj] = -theta * ra;
+                work.b[jj] = 1.0 + theta * (ra + rb);
+                work.c[jj] = -theta * rb;
+                work.d[jj] =
+                    t[ii] + (1.0 - theta) * (ra * (above - t[ii]) + rb * (t[ii + 1] - t[ii]));
+                above = t[ii];
+            }
+            let (slope, offset) = bottom.linear(k_bottom, dxb);
+            work.b[0] += work.a[0] * linear[1];
+            work.c[0] += work.a[0] * linear[2];
+            work.d[0] -= work.a[0] * linear[0];
+            work.b[m - 1] += work.c[m - 1] * slope;
+            work.d[m - 1] -= work.c[m - 1] * offset;
+            let Workspace { a, b, c, d, .. } = work;
+            crate::math::tridiagonal_inplace(&a[..m], &b[..m], &mut c[..m], &mut d[..m]);
+            for jj in 0..m {
+                t[jj + 1] = work.d[jj];
+            }
+            t[0] = linear[0] + linear[1] * t[1] + linear[2] * t[2];
+        }
+    }
+
+    bottom.apply(t, k_bottom, dxb);
+    Ok(())
 }
 
 pub(crate) mod py {
     use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
     use pyo3::prelude::*;
 
-    use crate::Float;
+    use crate::{Float, py::tpm::properties::Properties};
+
+    fn bottom_boundary(
+        flux: Option<Float>,
+        temperature: Option<Float>,
+    ) -> PyResult<super::BottomBoundary> {
+        match (flux, temperature) {
+            (None, None) => Ok(super::BottomBoundary::Adiabatic),
+            (Some(q), None) => Ok(super::BottomBoundary::Flux(q)),
+            (None, Some(t)) => Ok(super::BottomBoundary::Temperature(t)),
+            (Some(_), Some(_)) => Err(pyo3::exceptions::PyValueError::new_err(
+                "bottom_flux and bottom_temperature are exclusive",
+            )),
+        }
+    }
+
+    // raise, reuse, or bounds (min, max) of the clamped estimate (K), see super::Fallback
+    #[derive(FromPyObject)]
+    pub enum Fallback {
+        Name(String),
+        Clamp(Float, Float),
+    }
+
+    pub(crate) fn surface_solver(
+        tolerance: Option<Float>,
+        fallback: Option<Fallback>,
+    ) -> PyResult<super::SurfaceSolver> {
+        let mut solver = super::SurfaceSolver::default();
+        if let Some(tolerance) = tolerance {
+            solver.tolerance = tolerance;
+        }
+        solver.fallback = match fallback {
+            None => super::Fallback::Raise,
+            Some(Fallback::Clamp(min, max)) => super::Fallback::Clamp(min, max),
+            Some(Fallback::Name(name)) => match name.to_lowercase().as_str() {
+                "raise" => super::Fallback::Raise,
+                "reuse" => super::Fallback::Reuse,
+                _ => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Unknown fallback {}, expected raise, reuse or bounds (min, max)",
+                        name
+                    )));
+                }
+            },
+        };
+        Ok(solver)
+    }
 
     #[pyfunction]
+    #[pyo3(signature = (t, f, d, dtpdx2, se, k, twodx, theta=None, bottom_flux=None, bottom_temperature=None, tolerance=None, fallback=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn update_thermal_state<'py>(
         py: Python<'py>,
         t: PyReadonlyArray1<'py, Float>,
@@ -36,16 +555,155 @@ pub(crate) mod py {
         se: Float,
         k: Float,
         twodx: Float,
-    ) -> Bound<'py, PyArray1<Float>> {
-        super::update_thermal_state(
+        theta: Option<Float>,
+        bottom_flux: Option<Float>,
+        bottom_temperature: Option<Float>,
+        tolerance: Option<Float>,
+        fallback: Option<Fallback>,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let t = super::update_thermal_state(
+            t.as_array(),
+            f,
+            d.as_array(),
+            super::Stencil::Uniform {
+                dtpdx2: dtpdx2.as_array(),
+                twodx,
+            },
+            se,
+            k,
+            super::ColumnSolver {
+                scheme: super::Scheme::from_theta(theta),
+                bottom: bottom_boundary(bottom_flux, bottom_temperature)?,
+                surface: surface_solver(tolerance, fallback)?,
+            },
+        )
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        Ok(t.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, f, d, dtpa, dtpb, se, k, g, dxb, theta=None, bottom_flux=None, bottom_temperature=None, tolerance=None, fallback=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_thermal_state_nonuniform<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        f: Float,
+        d: PyReadonlyArray1<'py, Float>,
+        dtpa: PyReadonlyArray1<'py, Float>,
+        dtpb: PyReadonlyArray1<'py, Float>,
+        se: Float,
+        k: Float,
+        g: [Float; 3],
+        dxb: Float,
+        theta: Option<Float>,
+        bottom_flux: Option<Float>,
+        bottom_temperature: Option<Float>,
+        tolerance: Option<Float>,
+        fallback: Option<Fallback>,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let t = super::update_thermal_state(
             t.as_array(),
             f,
             d.as_array(),
-            dtpdx2.as_array(),
+            super::Stencil::Nonuniform {
+                dtpa: dtpa.as_array(),
+                dtpb: dtpb.as_array(),
+                g,
+                dxb,
+            },
             se,
             k,
-            twodx,
+            super::ColumnSolver {
+                scheme: super::Scheme::from_theta(theta),
+                bottom: bottom_boundary(bottom_flux, bottom_temperature)?,
+                surface: surface_solver(tolerance, fallback)?,
+            },
+        )
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        Ok(t.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, f, properties, dtpa, dtpb, g, dxb, theta=None, bottom_flux=None, bottom_temperature=None, tolerance=None, fallback=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_thermal_state_properties<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        f: Float,
+        properties: PyRef<'py, Properties>,
+        dtpa: PyReadonlyArray1<'py, Float>,
+        dtpb: PyReadonlyArray1<'py, Float>,
+        g: [Float; 3],
+        dxb: Float,
+        theta: Option<Float>,
+        bottom_flux: Option<Float>,
+        bottom_temperature: Option<Float>,
+        tolerance: Option<Float>,
+        fallback: Option<Fallback>,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let t = super::update_thermal_state_properties(
+            t.as_array(),
+            f,
+            &properties.inner.borrow(),
+            super::Stencil::Nonuniform {
+                dtpa: dtpa.as_array(),
+                dtpb: dtpb.as_array(),
+                g,
+                dxb,
+            },
+            super::ColumnSolver {
+                scheme: super::Scheme::from_theta(theta),
+                bottom: bottom_boundary(bottom_flux, bottom_temperature)?,
+                surface: surface_solver(tolerance, fallback)?,
+            },
+        )
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        Ok(t.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (t, f, p, c, k, properties, dtpa, dtpb, g, dxb, theta=None, bottom_flux=None, bottom_temperature=None, tolerance=None, fallback=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_thermal_state_layered<'py>(
+        py: Python<'py>,
+        t: PyReadonlyArray1<'py, Float>,
+        f: Float,
+        p: PyReadonlyArray1<'py, Float>,
+        c: PyReadonlyArray1<'py, Float>,
+        k: PyReadonlyArray1<'py, Float>,
+        properties: PyRef<'py, Properties>,
+        dtpa: PyReadonlyArray1<'py, Float>,
+        dtpb: PyReadonlyArray1<'py, Float>,
+        g: [Float; 3],
+        dxb: Float,
+        theta: Option<Float>,
+        bottom_flux: Option<Float>,
+        bottom_temperature: Option<Float>,
+        tolerance: Option<Float>,
+        fallback: Option<Fallback>,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        let t = super::update_thermal_state_layered(
+            t.as_array(),
+            f,
+            super::Layers {
+                p: p.as_array(),
+                c: c.as_array(),
+                k: k.as_array(),
+            },
+            &properties.inner.borrow(),
+            super::Stencil::Nonuniform {
+                dtpa: dtpa.as_array(),
+                dtpb: dtpb.as_array(),
+                g,
+                dxb,
+            },
+            super::ColumnSolver {
+                scheme: super::Scheme::from_theta(theta),
+                bottom: bottom_boundary(bottom_flux, bottom_temperature)?,
+                surface: surface_solver(tolerance, fallback)?,
+            },
         )
-        .to_pyarray(py)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        Ok(t.to_pyarray(py))
     }
 }
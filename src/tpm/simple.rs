@@ -0,0 +1,271 @@
+use anyhow::{Result, anyhow};
+use numpy::ndarray::{Array1, ArrayView1};
+use pyo3::prelude::*;
+
+use super::emit::{irradiance, planck, spectral_radiance, steradian};
+use crate::{Float, Vec3, mesh::Facet};
+
+// Beaming parameter of the Standard Thermal Model.
+pub const STM_BEAMING: Float = 0.756;
+
+// Phase coefficient of the Standard Thermal Model (mag/deg).
+pub const STM_PHASE_COEFFICIENT: Float = 0.01;
+
+// Simple thermal models of asteroids, with temperatures set by the instantaneous radiative
+// equilibrium instead of conduction. Each facet emits as a Lambertian black body times its
+// emissivity, and a facet does not hide the others, the shape is assumed convex.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThermalModel {
+    // Standard Thermal Model (Lebofsky et al. 1986): non-rotating body with the subsolar
+    // temperature lowered by the beaming parameter 0.756, observed at zero phase and corrected
+    // by 0.01 mag/deg.
+    Stm,
+
+    // Near-Earth Asteroid Thermal Model (Harris 1998): non-rotating body observed at its phase
+    // angle, the beaming parameter is fitted to the observations.
+    Neatm { eta: Float },
+
+    // Fast Rotating Model (Lebofsky & Spencer 1989): isothermal latitudes about the spin axis,
+    // the Sun in the equatorial plane.
+    Frm,
+}
+
+impl ThermalModel {
+    // name: stm, neatm or frm
+    // eta: beaming parameter of the NEATM
+    pub fn from_name(name: &str, eta: Float) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "stm" => Ok(Self::Stm),
+            "neatm" => Ok(Self::Neatm { eta }),
+            "frm" => Ok(Self::Frm),
+            _ => Err(anyhow!(
+                "Unknown thermal model {}, expected stm, neatm or frm",
+                name
+            )),
+        }
+    }
+
+    // Highest temperature of the surface (K), at the subsolar point or along the equator for
+    // the FRM, see core::effective_temperature.
+    //
+    // dau: distance to the Sun (AU)
+    // a: bond albedo
+    // e: emissivity
+    pub fn subsolar_temperature(&self, dau: Float, a: Float, e: Float) -> Float {
+        let r = match *self {
+            Self::Stm => 1.0 / STM_BEAMING,
+            Self::Neatm { eta } => 1.0 / eta,
+            Self::Frm => 1.0 / crate::util::PI,
+        };
+        super::core::effective_temperature(dau, r, a, e)
+    }
+}
+
+pub fn temperatures(
+    model: ThermalModel,
+    facets: &[Facet],
+    sun: Vec3,
+    spin_axis: Vec3,
+    a: Float,
+    e: Float,
+) -> Array1<Float> {
+    // Surface temperature of each facet (K). For the STM and NEATM, T = T_ss cos(i)^1/4 on the
+    // day side and 0 on the night side. For the FRM, T = T_max cos(latitude)^1/4.
+    //
+    // sun: position of the Sun in the frame of the facets (m)
+    // spin_axis: in the frame of the facets, only used by the FRM
+    // a: bond albedo
+    // e: emissivity
+    let t0 = model.subsolar_temperature(sun.length() / crate::util::AU, a, e);
+    let (u_sun, spin_axis) = (sun.normalize(), spin_axis.normalize());
+    facets
+        .iter()
+        .map(|facet| match model {
+            ThermalModel::Frm => {
+                let sinl = facet.normal.dot(spin_axis);
+                t0 * (1.0 - sinl * sinl).max(0.0).powf(0.125)
+            }
+            _ => t0 * facet.normal.dot(u_sun).max(0.0).powf(0.25),
+        })
+        .collect()
+}
+
+// Positions of the Sun and of the observer in the frame of the facets (m).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Geometry {
+    pub sun: Vec3,
+    pub observer: Vec3,
+}
+
+pub fn flux(
+    model: ThermalModel,
+    facets: &[Facet],
+    geometry: Geometry,
+    spin_axis: Vec3,
+    a: Float,
+    e: Float,
+    w: ArrayView1<Float>,
+) -> Array1<Float> {
+    // Thermal emission of a body received by an observer at each wavelength (W/m3), see
+    // emit::spectral_flux_density. For the STM, the observer is moved to zero phase at the same
+    // distance and the flux is reduced by STM_PHASE_COEFFICIENT times the phase angle.
+    //
+    // facets: surface of the body, e.g. mesh::Mesh::facets or the ellipsoid of
+    // entity::Body::radii with planetshine::ellipsoid_elements
+    // spin_axis: in the frame of the facets, only used by the FRM
+    // a: bond albedo
+    // e: emissivity
+    // w: wavelengths (m)
+    let Geometry { sun, observer } = geometry;
+    let t = temperatures(model, facets, sun, spin_axis, a, e);
+    let (observer, correction) = match model {
+        ThermalModel::Stm => {
+            let phase = sun.normalize().angle_between(observer.normalize()) * crate::util::DPR;
+            (
+                sun.normalize() * observer.length(),
+                (10.0 as Float).powf(-0.4 * STM_PHASE_COEFFICIENT * phase),
+            )
+        }
+        _ => (observer, 1.0),
+    };
+    let mut flux = Array1::zeros(w.len());
+    for (ii, facet) in facets.iter().enumerate() {
+        let v = observer - facet.pos;
+        let d = v.length();
+        let cose = facet.normal.dot(v / d);
+        if cose <= 0.0 || t[ii] <= 0.0 {
+            continue;
+        }
+        let sr = steradian(facet.area, d);
+        for (f, &w) in flux.iter_mut().zip(w) {
+            *f += irradiance(spectral_radiance(planck(t[ii], w), e, cose, 1.0), sr);
+        }
+    }
+    flux * correction
+}
+
+pub fn flux_ellipsoid(
+    model: ThermalModel,
+    radii: Vec3,
+    resolution: usize,
+    geometry: Geometry,
+    a: Float,
+    e: Float,
+    w: ArrayView1<Float>,
+) -> Array1<Float> {
+    // Flux of an ellipsoid spinning about its shortest axis z, see flux.
+    //
+    // radii: e.g. entity::Body::radii (m)
+    // resolution: see planetshine::ellipsoid_elements
+    let facets = super::planetshine::ellipsoid_elements(radii, resolution);
+    flux(model, &facets, geometry, Vec3::Z, a, e, w)
+}
+
+#[pyfunction]
+pub fn bond_albedo(pv: Float, g: Float) -> Float {
+    // pv: geometric albedo in the V band
+    // g: slope parameter of the H, G magnitude system
+    pv * (0.290 + 0.684 * g)
+}
+
+#[pyfunction]
+pub fn diameter(h: Float, pv: Float) -> Float {
+    // h: absolute magnitude in the V band
+    // pv: geometric albedo in the V band
+    //
+    // output diameter of the sphere of same cross-section (m)
+    1329e3 / pv.sqrt() * (10.0 as Float).powf(-h / 5.0)
+}
+
+pub(crate) mod py {
+    use numpy::{PyArray1, PyReadonlyArray1, ToPyArray};
+    use pyo3::exceptions::PyValueError;
+    use pyo3::prelude::*;
+
+    use super::{Geometry, ThermalModel};
+    use crate::{Float, Vec3, py::mesh::Mesh};
+
+    fn thermal_model(model: &str, eta: Float) -> PyResult<ThermalModel> {
+        ThermalModel::from_name(model, eta).map_err(|e| PyValueError::new_err(format!("{:#}", e)))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (model, dau, a, e, eta=1.0))]
+    pub fn subsolar_temperature(
+        model: &str,
+        dau: Float,
+        a: Float,
+        e: Float,
+        eta: Float,
+    ) -> PyResult<Float> {
+        // model: stm, neatm or frm
+        // eta: beaming parameter of the NEATM
+        Ok(thermal_model(model, eta)?.subsolar_temperature(dau, a, e))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (model, mesh, sun, observer, a, e, w, eta=1.0, spin_axis=[0.0, 0.0, 1.0]))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn flux<'py>(
+        py: Python<'py>,
+        model: &str,
+        mesh: Bound<'py, Mesh>,
+        sun: PyReadonlyArray1<'py, Float>,
+        observer: PyReadonlyArray1<'py, Float>,
+        a: Float,
+        e: Float,
+        w: PyReadonlyArray1<'py, Float>,
+        eta: Float,
+        spin_axis: [Float; 3],
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        // Spectral flux density of a mesh (W/m3).
+        //
+        // sun, observer: positions in the frame of the mesh (m)
+        let flux = super::flux(
+            thermal_model(model, eta)?,
+            &mesh.borrow().inner.borrow().facets,
+            Geometry {
+                sun: Vec3::from_slice(sun.as_slice()?),
+                observer: Vec3::from_slice(observer.as_slice()?),
+            },
+            Vec3::from_array(spin_axis),
+            a,
+            e,
+            w.as_array(),
+        );
+        Ok(flux.to_pyarray(py))
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (model, radii, sun, observer, a, e, w, eta=1.0, resolution=64))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn flux_ellipsoid<'py>(
+        py: Python<'py>,
+        model: &str,
+        radii: [Float; 3],
+        sun: PyReadonlyArray1<'py, Float>,
+        observer: PyReadonlyArray1<'py, Float>,
+        a: Float,
+        e: Float,
+        w: PyReadonlyArray1<'py, Float>,
+        eta: Float,
+        resolution: usize,
+    ) -> PyResult<Bound<'py, PyArray1<Float>>> {
+        // Spectral flux density of an ellipsoid spinning about z (W/m3).
+        //
+        // radii: e.g. Body.radii (m)
+        let flux = super::flux_ellipsoid(
+            thermal_model(model, eta)?,
+            Vec3::from_array(radii),
+            resolution,
+            Geometry {
+                sun: Vec3::from_slice(sun.as_slice()?),
+                observer: Vec3::from_slice(observer.as_slice()?),
+            },
+            a,
+            e,
+            w.as_array(),
+        );
+        Ok(flux.to_pyarray(py))
+    }
+}
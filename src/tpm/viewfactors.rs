@@ -0,0 +1,290 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, anyhow};
+use numpy::ndarray::{Array1, Array2, ArrayView1, ArrayView2};
+
+use crate::{Float, mesh::Mesh};
+
+// View factor matrix stored by rows (compressed sparse row), only the pairs of facets that see
+// each other are kept. The view factors of row i are values[indptr[i]..indptr[i + 1]] for the
+// facets indices[indptr[i]..indptr[i + 1]], sorted.
+#[derive(Clone, PartialEq)]
+pub struct ViewFactors {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<u32>,
+    pub values: Vec<Float>,
+}
+
+// Estimator of the view factors between facets, see tpm::heating::view_factor_matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ViewFactorMethod {
+    // Formula between the centres of the facets, close facets are subdivided, see
+    // mesh::view_factor_triangles.
+    #[default]
+    Analytic,
+
+    // Rays traced from random points of each facet in cosine-weighted directions, the view
+    // factor to another facet is the fraction of the rays hitting it. The error decreases as the
+    // inverse square root of the number of rays. The same seed gives the same matrix whatever the
+    // number of threads.
+    MonteCarlo {
+        rays: usize,
+        seed: u64,
+    },
+}
+
+impl ViewFactorMethod {
+    pub fn from_rays(rays: Option<usize>, seed: u64) -> Self {
+        rays.map_or(Self::Analytic, |rays| Self::MonteCarlo { rays, seed })
+    }
+}
+
+// Binary file of a view factor matrix, little-endian:
+//
+// magic (8 bytes), version (u32), size of Float in bytes (u8), key (u64), nrows, ncols and
+// number of values (u64), indptr (u64), indices (u32) and values (Float).
+pub const MAGIC: &[u8; 8] = b"KALASTVF";
+
+// Version of the format written, increased when the layout changes.
+pub const VERSION: u32 = 1;
+
+impl ViewFactors {
+    pub fn zeros(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            indptr: vec![0; nrows + 1],
+            indices: vec![],
+            values: vec![],
+        }
+    }
+
+    // Matrix from its non-zero values (row, column, value), in any order. Duplicates are summed.
+    pub fn from_triplets(nrows: usize, ncols: usize, mut triplets: Vec<(u32, u32, Float)>) -> Self {
+        triplets.sort_unstable_by_key(|&(ii, jj, _)| (ii, jj));
+        let mut vf = Self::zeros(nrows, ncols);
+        for (ii, jj, v) in triplets {
+            if vf.indptr[ii as usize + 1] > 0 && vf.indices.last() == Some(&jj) {
+                *vf.values.last_mut().unwrap() += v;
+                continue;
+            }
+            vf.indices.push(jj);
+            vf.values.push(v);
+            vf.indptr[ii as usize + 1] += 1;
+        }
+        for ii in 0..nrows {
+            vf.indptr[ii + 1] += vf.indptr[ii];
+        }
+        vf
+    }
+
+    // Sparse matrix from a dense one, values not above cutoff are removed.
+    pub fn from_dense(a: ArrayView2<'_, Float>, cutoff: Float) -> Self {
+        let triplets = a
+            .indexed_iter()
+            .filter(|&(_, &v)| v > cutoff)
+            .map(|((ii, jj), &v)| (ii as u32, jj as u32, v))
+            .collect();
+        Self::from_triplets(a.nrows(), a.ncols(), triplets)
+    }
+
+    // Matrix without the values not above cutoff.
+    pub fn pruned(&self, cutoff: Float) -> Self {
+        let mut vf = Self::zeros(self.nrows, self.ncols);
+        for ii in 0..self.nrows {
+            let (indices, values) = self.row(ii);
+            for (&jj, &v) in indices.iter().zip(values) {
+                if v > cutoff {
+                    vf.indices.push(jj);
+                    vf.values.push(v);
+                }
+            }
+            vf.indptr[ii + 1] = vf.indices.len();
+        }
+        vf
+    }
+
+    pub fn to_dense(&self) -> Array2<Float> {
+        let mut a = Array2::zeros((self.nrows, self.ncols));
+        for ii in 0..self.nrows {
+            let (indices, values) = self.row(ii);
+            for (&jj, &v) in indices.iter().zip(values) {
+                a[[ii, jj as usize]] = v;
+            }
+        }
+        a
+    }
+
+    // Number of stored values.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn row(&self, ii: usize) -> (&[u32], &[Float]) {
+        let range = self.indptr[ii]..self.indptr[ii + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    // Matrix-vector product, the flux received by each facet of the rows from the exitance of
+    // the facets of the columns.
+    pub fn dot(&self, x: ArrayView1<'_, Float>) -> Array1<Float> {
+        Array1::from_shape_fn(self.nrows, |ii| {
+            let (indices, values) = self.row(ii);
+            indices
+                .iter()
+                .zip(values)
+                .map(|(&jj, &v)| v * x[jj as usize])
+                .sum()
+        })
+    }
+
+    // Write the matrix to a file with a key identifying what it was computed from, see
+    // cache_key.
+    pub fn save(&self, path: impl AsRef<Path>, key: u64) -> Result<()> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Cannot create view factors {}", path.display()))?;
+        let mut w = BufWriter::new(file);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&[size_of::<Float>() as u8])?;
+        w.write_all(&key.to_le_bytes())?;
+        for n in [self.nrows, self.ncols, self.nnz()] {
+            w.write_all(&(n as u64).to_le_bytes())?;
+        }
+        for &x in &self.indptr {
+            w.write_all(&(x as u64).to_le_bytes())?;
+        }
+        for &x in &self.indices {
+            w.write_all(&x.to_le_bytes())?;
+        }
+        for &x in &self.values {
+            w.write_all(&x.to_le_bytes())?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    // Read a file written by `save`, fails if it was saved with another key.
+    pub fn load(path: impl AsRef<Path>, key: u64) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Cannot open view factors {}", path.display()))?;
+        read(&mut BufReader::new(file), key)
+            .with_context(|| format!("Cannot read view factors {}", path.display()))
+    }
+}
+
+impl std::fmt::Debug for ViewFactors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ViewFactors(nrows={}, ncols={}, nnz={})",
+            self.nrows,
+            self.ncols,
+            self.nnz(),
+        )
+    }
+}
+
+fn read<R: Read>(r: &mut R, key: u64) -> Result<ViewFactors> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(anyhow!("Not a view factors file"));
+    }
+    let version = u32::from_le_bytes(read_bytes(r)?);
+    if version != VERSION {
+        return Err(anyhow!(
+            "View factors version {} is not supported, expected {}",
+            version,
+            VERSION
+        ));
+    }
+    let [size] = read_bytes::<_, 1>(r)?;
+    if size as usize != size_of::<Float>() {
+        return Err(anyhow!(
+            "View factors written with floats of {} bytes, expected {}",
+            size,
+            size_of::<Float>()
+        ));
+    }
+    let saved = u64::from_le_bytes(read_bytes(r)?);
+    if saved != key {
+        return Err(anyhow!(
+            "View factors computed for another mesh or parameters"
+        ));
+    }
+    let mut read_usize = || -> Result<usize> { Ok(u64::from_le_bytes(read_bytes(r)?) as usize) };
+    let (nrows, ncols, nnz) = (read_usize()?, read_usize()?, read_usize()?);
+    let indptr = (0..=nrows)
+        .map(|_| read_usize())
+        .collect::<Result<Vec<_>>>()?;
+    let indices = (0..nnz)
+        .map(|_| Ok(u32::from_le_bytes(read_bytes(r)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let values = (0..nnz)
+        .map(|_| Ok(Float::from_le_bytes(read_bytes(r)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if indptr[0] != 0
+        || indptr.windows(2).any(|w| w[0] > w[1])
+        || indptr[nrows] != nnz
+        || indices.iter().any(|&jj| jj as usize >= ncols)
+    {
+        return Err(anyhow!("View factors file is corrupted"));
+    }
+    Ok(ViewFactors {
+        nrows,
+        ncols,
+        indptr,
+        indices,
+        values,
+    })
+}
+
+fn read_bytes<R: Read, const N: usize>(r: &mut R) -> Result<[u8; N]> {
+    let mut b = [0u8; N];
+    r.read_exact(&mut b)?;
+    Ok(b)
+}
+
+// FNV-1a hash of bytes, stable between runs and platforms unlike the hasher of std.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+// Hash of the vertices of the facets of a mesh.
+pub fn mesh_hash(mesh: &Mesh) -> u64 {
+    mesh.triangles()
+        .iter()
+        .flatten()
+        .fold(0xcbf29ce484222325, |h, p| {
+            p.to_array()
+                .iter()
+                .fold(h, |h, x| fnv1a(h, &x.to_le_bytes()))
+        })
+}
+
+// Key of a view factor matrix of a mesh computed with some parameters, see
+// tpm::heating::view_factor_matrix.
+pub fn cache_key(mesh: &Mesh, method: ViewFactorMethod, occlusion: bool, cutoff: Float) -> u64 {
+    let h = fnv1a(mesh_hash(mesh), &[occlusion as u8]);
+    let h = fnv1a(h, &cutoff.to_le_bytes());
+    match method {
+        ViewFactorMethod::Analytic => {
+            let h = fnv1a(h, &crate::mesh::VIEW_FACTOR_NEAR_FIELD.to_le_bytes());
+            fnv1a(h, &[crate::mesh::VIEW_FACTOR_MAX_DEPTH as u8])
+        }
+        ViewFactorMethod::MonteCarlo { rays, seed } => {
+            let h = fnv1a(h, &(rays as u64).to_le_bytes());
+            fnv1a(h, &seed.to_le_bytes())
+        }
+    }
+}